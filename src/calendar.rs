@@ -1,18 +1,24 @@
 use std::{
-    cell::{OnceCell, RefCell},
+    cell::{Cell, OnceCell, RefCell},
+    collections::HashMap,
+    marker::PhantomData,
+    rc::Rc,
     sync::LazyLock,
 };
 
 use gdk::{
     RGBA,
-    gio::ListStore,
+    gio::{self, ListStore},
     glib::{self, Object, clone, closure_local, subclass::Signal},
     prelude::*,
     subclass::prelude::*,
 };
 use tracing::info;
 
-use crate::{Collection, Event, Manager};
+use crate::{
+    Collection, Event, Manager, NewEvent, UndoAction, WriteError, Zoned, csv, locale_first_weekday,
+    month_grid_range, utils::SignalWatches, week_range,
+};
 
 mod imp {
     use super::*;
@@ -20,10 +26,11 @@ mod imp {
     #[derive(Debug, Default, glib::Properties)]
     #[properties(wrapper_type = super::Calendar)]
     pub struct Calendar {
-        #[property(get, construct_only)]
-        manager: OnceCell<Manager>,
-        #[property(get, construct_only)]
-        collection: OnceCell<Collection>,
+        /// The manager of this calendar, `None` for detached calendars.
+        #[property(get, construct_only, nullable)]
+        manager: RefCell<Option<Manager>>,
+        #[property(get = Self::collection, set = Self::set_collection, construct_only, type = Collection)]
+        collection: RefCell<Option<Collection>>,
         #[property(get, construct_only)]
         uri: OnceCell<String>,
         #[property(get, set, explicit_notify)]
@@ -33,6 +40,39 @@ mod imp {
         color: RefCell<Option<RGBA>>,
         #[property(get)]
         events: OnceCell<ListStore>,
+        /// Whether this calendar cannot be written to, such as a subscription.
+        #[property(get)]
+        pub(super) read_only: Cell<bool>,
+        /// The URL of the remote feed this calendar is subscribed to, if any.
+        #[property(get, nullable)]
+        pub(super) subscription_url: RefCell<Option<String>>,
+        /// When the backend last refreshed the subscription, if it ever did.
+        #[property(get, nullable)]
+        pub(super) last_refreshed: RefCell<Option<Zoned>>,
+        /// The token identifying the state of this calendar on the remote server, if any.
+        #[property(get, nullable)]
+        pub(super) sync_token: RefCell<Option<String>>,
+        /// The position of this calendar in its collection set by the user, or 0 if it has none.
+        ///
+        /// Calendars are sorted by this position, then by name.
+        #[property(get)]
+        pub(super) sort_order: Cell<i32>,
+        /// The URL this calendar is synced from, such as a CalDAV URL, if known.
+        #[property(get, nullable)]
+        pub(super) source_url: RefCell<Option<String>>,
+        /// The description of this calendar, empty if it has none.
+        #[property(get)]
+        pub(super) description: RefCell<String>,
+        /// Whether the user chose to show the events of this calendar.
+        ///
+        /// This is only kept in memory, see [`Manager::visibility_state`] to persist it.
+        #[property(get, set, construct, default = true)]
+        visible: Cell<bool>,
+        /// Whether this calendar has at least one event.
+        #[property(get = Self::has_events, type = bool)]
+        has_events: PhantomData<bool>,
+        /// A copy of the events of this calendar, to know which ones are removed from the model.
+        events_snapshot: RefCell<Vec<Event>>,
     }
 
     #[glib::object_subclass]
@@ -42,17 +82,66 @@ mod imp {
         type ParentType = Object;
     }
 
+    impl Calendar {
+        fn collection(&self) -> Collection {
+            self.collection
+                .borrow()
+                .clone()
+                .expect("Calendar should have a collection")
+        }
+
+        pub(super) fn set_collection(&self, collection: Collection) {
+            self.collection.replace(Some(collection));
+        }
+    }
+
     #[glib::derived_properties]
     impl ObjectImpl for Calendar {
         fn constructed(&self) {
             self.parent_constructed();
 
-            self.events.get_or_init(ListStore::new::<Event>);
+            let events = self.events.get_or_init(ListStore::new::<Event>);
+            events.connect_items_changed(clone!(
+                #[weak(rename_to = obj)]
+                self.obj(),
+                move |events, position, removed, added| {
+                    let n_items = events.n_items();
+                    let previous_n_items = n_items + removed - added;
+                    if (n_items == 0) != (previous_n_items == 0) {
+                        obj.notify_has_events();
+                    }
+
+                    let added_events = (position..position + added)
+                        .filter_map(|position| events.item(position).and_downcast::<Event>())
+                        .collect::<Vec<_>>();
+                    let removed_events = obj
+                        .imp()
+                        .events_snapshot
+                        .borrow_mut()
+                        .splice(
+                            position as usize..(position + removed) as usize,
+                            added_events.iter().cloned(),
+                        )
+                        .collect::<Vec<_>>();
+
+                    let added_store = ListStore::new::<Event>();
+                    added_store.extend_from_slice(&added_events);
+                    let removed_store = ListStore::new::<Event>();
+                    removed_store.extend_from_slice(&removed_events);
+                    obj.emit_by_name::<()>("events-changed", &[&added_store, &removed_store]);
+                }
+            ));
         }
 
         fn signals() -> &'static [Signal] {
-            static SIGNALS: LazyLock<Vec<Signal>> =
-                LazyLock::new(|| vec![Signal::builder("deleted").build()]);
+            static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
+                vec![
+                    Signal::builder("deleted").build(),
+                    Signal::builder("events-changed")
+                        .param_types([ListStore::static_type(), ListStore::static_type()])
+                        .build(),
+                ]
+            });
             SIGNALS.as_ref()
         }
     }
@@ -61,6 +150,10 @@ mod imp {
         pub fn events(&self) -> &ListStore {
             self.events.get().expect("events should be initialized")
         }
+
+        fn has_events(&self) -> bool {
+            self.events().n_items() > 0
+        }
     }
 }
 
@@ -77,8 +170,27 @@ impl Calendar {
         name: &str,
         color: gdk::RGBA,
     ) -> Self {
-        glib::Object::builder()
+        let calendar: Self = glib::Object::builder()
             .property("manager", manager)
+            .property("collection", collection)
+            .property("uri", uri)
+            .property("name", name)
+            .property("color", Some(color))
+            .build();
+        calendar.connect_visible_notify(clone!(
+            #[weak]
+            manager,
+            move |_| manager.calendar_visibility_changed()
+        ));
+        calendar
+    }
+
+    /// Create a calendar of `collection` without a manager, for tests.
+    ///
+    /// Its writes fail with [`WriteError::Detached`].
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn new_detached(collection: &Collection, uri: &str, name: &str, color: gdk::RGBA) -> Self {
+        glib::Object::builder()
             .property("collection", collection)
             .property("uri", uri)
             .property("name", name)
@@ -86,11 +198,71 @@ impl Calendar {
             .build()
     }
 
+    /// The manager to write through.
+    fn writable_manager(&self) -> Result<Manager, WriteError> {
+        self.manager().ok_or(WriteError::Detached)
+    }
+
     /// Ask the backend to update this calendar. Properties with a None value will be left
     /// unchanged.
-    pub fn update(&self, name: Option<&str>, color: Option<gdk::RGBA>) {
+    pub fn update(&self, name: Option<&str>, color: Option<gdk::RGBA>) -> Result<(), WriteError> {
+        let undo_action = self.update_undo_action(name, color);
+
         // TODO: dispatch to relevant provider instead
-        self.manager().update_calendar(&self.uri(), name, color);
+        self.writable_manager()?
+            .update_calendar(&self.uri(), name, color)?;
+
+        self.writable_manager()?.record_undo(undo_action);
+        Ok(())
+    }
+
+    /// Ask the backend to update this calendar asynchronously, retrying transient failures.
+    ///
+    /// See [`Calendar::update`].
+    pub async fn update_future(
+        &self,
+        name: Option<&str>,
+        color: Option<gdk::RGBA>,
+    ) -> Result<(), WriteError> {
+        let undo_action = self.update_undo_action(name, color);
+
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?
+            .update_calendar_future(&self.uri(), name, color)
+            .await?;
+
+        self.writable_manager()?.record_undo(undo_action);
+        Ok(())
+    }
+
+    /// The action undoing an update of this calendar, from its current state.
+    fn update_undo_action(&self, name: Option<&str>, color: Option<gdk::RGBA>) -> UndoAction {
+        let old_name = self.name();
+        let old_color = self.color().unwrap_or(RGBA::BLACK);
+
+        UndoAction::UpdateCalendar {
+            uri: self.uri(),
+            new_name: name.map_or_else(|| old_name.clone(), str::to_string),
+            new_color: color.unwrap_or(old_color),
+            old_name,
+            old_color,
+        }
+    }
+
+    /// Update the name and the color of this calendar, unless another client renamed it.
+    ///
+    /// The name is read again from the database and compared to `last_known_name`, usually the
+    /// name the calendar had when the user started editing it. If they differ, or if the
+    /// calendar was deleted, nothing is written and [`WriteError::Conflict`] is returned.
+    pub fn update_if_unchanged(
+        &self,
+        last_known_name: &str,
+        name: Option<&str>,
+        color: Option<gdk::RGBA>,
+    ) -> Result<(), WriteError> {
+        self.writable_manager()?
+            .check_calendar_name(&self.uri(), last_known_name)?;
+        self.update(name, color)
     }
 
     /// TODO
@@ -108,15 +280,169 @@ impl Calendar {
         }
     }
 
-    /// Ask the backend to delete this calendar.
-    pub fn delete(&self) {
+    /// Ask the backend to move this calendar to another collection.
+    ///
+    /// Calendars can only be moved between collections of the same provider, other moves fail
+    /// with [`WriteError::Unsupported`].
+    pub fn move_to_collection(&self, target: &Collection) -> Result<(), WriteError> {
+        if target.provider() != self.collection().provider() {
+            return Err(WriteError::Unsupported);
+        }
+
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?
+            .update_calendar_collection(&self.uri(), &target.uri())
+    }
+
+    /// Move this calendar to the given collection, as reported by the backend.
+    pub(crate) fn emit_moved(&self, target: &Collection) {
+        let source = self.collection();
+        source.remove_calendar(self);
+        self.imp().set_collection(target.clone());
+        target.add_calendar(self);
+        info!(
+            "Calendar {} moved from collection {} to {}",
+            self.uri(),
+            source.uri(),
+            target.uri()
+        );
+        self.notify_collection();
+    }
+
+    /// Apply the subscription metadata retrieved from the backend, notifying the properties that
+    /// changed.
+    ///
+    /// Subscribed calendars are read-only, as the backend overwrites them on each refresh.
+    pub(crate) fn emit_subscription_updated(
+        &self,
+        subscription_url: Option<&str>,
+        last_refreshed: Option<&Zoned>,
+    ) {
+        if subscription_url != self.imp().subscription_url.borrow().as_deref() {
+            self.imp()
+                .subscription_url
+                .replace(subscription_url.map(str::to_string));
+            self.notify_subscription_url();
+        }
+        if last_refreshed != self.imp().last_refreshed.borrow().as_ref() {
+            self.imp().last_refreshed.replace(last_refreshed.cloned());
+            self.notify_last_refreshed();
+        }
+        let read_only = subscription_url.is_some();
+        if self.imp().read_only.replace(read_only) != read_only {
+            self.notify_read_only();
+        }
+    }
+
+    /// Apply the sync token retrieved from the backend, notifying if it changed.
+    pub(crate) fn emit_sync_token_updated(&self, sync_token: Option<&str>) {
+        if sync_token != self.imp().sync_token.borrow().as_deref() {
+            self.imp()
+                .sync_token
+                .replace(sync_token.map(str::to_string));
+            self.notify_sync_token();
+        }
+    }
+
+    /// Apply the source URL and the description retrieved from the backend, notifying if they
+    /// changed.
+    pub(crate) fn emit_details_updated(&self, source_url: Option<&str>, description: &str) {
+        if source_url != self.imp().source_url.borrow().as_deref() {
+            self.imp()
+                .source_url
+                .replace(source_url.map(str::to_string));
+            self.notify_source_url();
+        }
+        if description != *self.imp().description.borrow() {
+            self.imp().description.replace(description.to_string());
+            self.notify_description();
+        }
+    }
+
+    /// Apply the position retrieved from the backend, notifying if it changed.
+    pub(crate) fn emit_sort_order_updated(&self, sort_order: i32) {
+        if self.imp().sort_order.replace(sort_order) != sort_order {
+            self.notify_sort_order();
+        }
+    }
+
+    /// Ask the backend to move this calendar to the trash.
+    ///
+    /// Trashed calendars disappear from their collection, and can be brought back with
+    /// [`Calendar::restore`]. Use [`Calendar::permanently_delete`] to remove it for good.
+    pub fn delete(&self) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?
+            .update_calendar_trashed(&self.uri(), true)?;
+
+        self.writable_manager()?
+            .record_undo(UndoAction::TrashCalendar { uri: self.uri() });
+        Ok(())
+    }
+
+    /// Ask the backend to move this calendar to the trash asynchronously, retrying transient
+    /// failures.
+    ///
+    /// See [`Calendar::delete`].
+    pub async fn delete_future(&self) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?
+            .update_calendar_trashed_future(&self.uri(), true)
+            .await?;
+
+        self.writable_manager()?
+            .record_undo(UndoAction::TrashCalendar { uri: self.uri() });
+        Ok(())
+    }
+
+    /// Ask the backend to take this calendar out of the trash.
+    pub fn restore(&self) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?
+            .update_calendar_trashed(&self.uri(), false)?;
+
+        self.writable_manager()?
+            .record_undo(UndoAction::RestoreCalendar { uri: self.uri() });
+        Ok(())
+    }
+
+    /// Ask the backend to take this calendar out of the trash asynchronously, retrying transient
+    /// failures.
+    pub async fn restore_future(&self) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?
+            .update_calendar_trashed_future(&self.uri(), false)
+            .await?;
+
+        self.writable_manager()?
+            .record_undo(UndoAction::RestoreCalendar { uri: self.uri() });
+        Ok(())
+    }
+
+    /// Ask the backend to delete this calendar, without going through the trash.
+    ///
+    /// This cannot be undone with [`Manager::undo`].
+    pub fn permanently_delete(&self) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?.delete_calendar(&self.uri())
+    }
+
+    /// Ask the backend to delete this calendar asynchronously, without going through the trash,
+    /// retrying transient failures.
+    ///
+    /// This cannot be undone with [`Manager::undo`].
+    pub async fn permanently_delete_future(&self) -> Result<(), WriteError> {
         // TODO: dispatch to relevant provider instead
-        self.manager().delete_calendar(&self.uri());
+        self.writable_manager()?
+            .delete_calendar_future(&self.uri())
+            .await
     }
 
     /// Signal that this calendar was deleted.
     pub(super) fn emit_deleted(&self) {
-        for event in self.events().iter::<Event>() {
+        // Deleted events remove themselves from the model, so collect them first
+        let events = self.events().iter::<Event>().collect::<Vec<_>>();
+        for event in events {
             event
                 .expect("Model should not be mutated during iteration")
                 .emit_deleted();
@@ -136,6 +462,29 @@ impl Calendar {
         )
     }
 
+    /// Connect to the signal emitted when events are added to or removed from this calendar.
+    ///
+    /// The handler receives the added events, then the removed ones. Moves within the model
+    /// report the same events as both added and removed.
+    pub fn connect_events_changed<F: Fn(&Self, &[Event], &[Event]) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "events-changed",
+            true,
+            closure_local!(|obj: Self, added: ListStore, removed: ListStore| {
+                let to_vec = |store: &ListStore| {
+                    store
+                        .iter::<Event>()
+                        .filter_map(Result::ok)
+                        .collect::<Vec<_>>()
+                };
+                f(&obj, &to_vec(&added), &to_vec(&removed));
+            }),
+        )
+    }
+
     /// Add an event to this calendar.
     pub(crate) fn add_event(&self, event: &Event) {
         self.imp().events().append(event);
@@ -150,9 +499,344 @@ impl Calendar {
         ));
     }
 
+    /// The events of this calendar sorted by start, then by end.
+    ///
+    /// Unlike a sorted list model, this does not need a main loop, which suits tools and tests.
+    /// Events without a time frame come last.
+    pub fn events_sorted_vec(&self) -> Vec<Event> {
+        let mut events = self
+            .events()
+            .iter::<Event>()
+            .map(|event| event.expect("Model should not be mutated during iteration"))
+            .collect::<Vec<_>>();
+        events.sort_by_cached_key(|event| match event.timeframe() {
+            Some(timeframe) => (
+                false,
+                Some(timeframe.start().0.timestamp()),
+                Some(timeframe.end().0.timestamp()),
+            ),
+            None => (true, None, None),
+        });
+        events
+    }
+
+    /// Group the events of this calendar that look like duplicates of each other.
+    ///
+    /// Events are duplicates if they have the same name, ignoring leading, trailing and repeated
+    /// whitespace, and the same time frame, comparing instants regardless of their time zone.
+    /// Only groups of at least two events are returned, in the order of the events.
+    pub fn find_duplicates(&self) -> Vec<Vec<Event>> {
+        let mut group_indices: HashMap<_, usize> = HashMap::new();
+        let mut groups: Vec<Vec<Event>> = Vec::new();
+        for event in self.events().iter::<Event>() {
+            let event = event.expect("Model should not be mutated during iteration");
+            let Some(timeframe) = event.timeframe() else {
+                continue;
+            };
+            let key = (
+                event
+                    .name()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                timeframe.all_day(),
+                timeframe.start().0.timestamp(),
+                timeframe.end().0.timestamp(),
+            );
+
+            match group_indices.get(&key) {
+                Some(&index) => groups[index].push(event),
+                None => {
+                    group_indices.insert(key, groups.len());
+                    groups.push(vec![event]);
+                }
+            }
+        }
+
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// Count the events of this calendar falling on each day of the month containing `month`.
+    ///
+    /// Events spanning several days are counted on each day they cover. Days without events are
+    /// absent from the map.
+    pub fn event_counts_by_day(&self, month: jiff::civil::Date) -> HashMap<jiff::civil::Date, u32> {
+        let first = month.first_of_month();
+        let last = month.last_of_month();
+
+        let mut counts = HashMap::new();
+        for event in self.events().iter::<Event>() {
+            let event = event.expect("Model should not be mutated during iteration");
+            let Some(timeframe) = event.timeframe() else {
+                continue;
+            };
+
+            for day in timeframe
+                .days()
+                .skip_while(|day| *day < first)
+                .take_while(|day| *day <= last)
+            {
+                *counts.entry(day).or_default() += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// Group the events of this calendar by week, for `weeks` weeks from the week containing
+    /// `start`.
+    ///
+    /// Each week is given by its first day, following [`week_range`], with the events covering
+    /// at least one of its days sorted by start. Events spanning several weeks are in each of
+    /// them, and weeks without events are kept.
+    pub fn events_by_week(
+        &self,
+        start: jiff::civil::Date,
+        weeks: u32,
+        week_start: jiff::civil::Weekday,
+    ) -> Vec<(jiff::civil::Date, Vec<Event>)> {
+        let events = self.events_sorted_vec();
+        let (first_week_start, _) = week_range(start, week_start);
+
+        first_week_start
+            .series(jiff::Span::new().weeks(1))
+            .take(weeks as usize)
+            .map(|week_first| {
+                let (week_first, week_last) = week_range(week_first, week_start);
+                let week_events = events
+                    .iter()
+                    .filter(|event| {
+                        event.timeframe().is_some_and(|timeframe| {
+                            timeframe
+                                .days()
+                                .skip_while(|day| *day < week_first)
+                                .take_while(|day| *day <= week_last)
+                                .next()
+                                .is_some()
+                        })
+                    })
+                    .cloned()
+                    .collect();
+                (week_first, week_events)
+            })
+            .collect()
+    }
+
+    /// Export the events of this calendar as CSV, for spreadsheets.
+    ///
+    /// There is a header row, then a row per event, sorted by start, with its name,
+    /// description, start, end, whether it is all-day, and location. Fields are quoted following
+    /// RFC 4180 when needed.
+    pub fn to_csv(&self) -> String {
+        csv::write_calendar(self)
+    }
+
+    /// Bucket the events of this calendar by day, for the month grid of the month containing
+    /// `month`.
+    ///
+    /// The model has one [`ListStore`] of events per day of the grid given by
+    /// [`month_grid_range`] with the [`locale_first_weekday`], in order. Events spanning several
+    /// days are in the bucket of each day they cover, and each bucket is sorted by start.
+    ///
+    /// The buckets are kept up to date as events are added, removed or moved, until the model is
+    /// dropped.
+    pub fn month_model(&self, month: jiff::civil::Date) -> gio::ListModel {
+        let (first, last) = month_grid_range(month, locale_first_weekday());
+        let days = ListStore::new::<ListStore>();
+        for _ in first
+            .series(jiff::Span::new().days(1))
+            .take_while(|day| *day <= last)
+        {
+            days.append(&ListStore::new::<Event>());
+        }
+
+        let watches = Rc::new(SignalWatches::default());
+        let days_weak = days.downgrade();
+        let refresh = Rc::new(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[strong]
+            watches,
+            move || {
+                let Some(days) = days_weak.upgrade() else {
+                    watches.clear();
+                    return;
+                };
+
+                let mut buckets = vec![Vec::new(); days.n_items() as usize];
+                for event in obj.events_sorted_vec() {
+                    let Some(timeframe) = event.timeframe() else {
+                        continue;
+                    };
+                    for day in timeframe
+                        .days()
+                        .skip_while(|day| *day < first)
+                        .take_while(|day| *day <= last)
+                    {
+                        let Ok(index) =
+                            usize::try_from(first.until(day).unwrap_or_default().get_days())
+                        else {
+                            continue;
+                        };
+                        buckets[index].push(event.clone());
+                    }
+                }
+
+                for (bucket, events) in days.iter::<ListStore>().zip(buckets) {
+                    let bucket = bucket.expect("Model should not be mutated during iteration");
+                    bucket.splice(0, bucket.n_items(), &events);
+                }
+            }
+        ));
+
+        let watch_event = clone!(
+            #[strong]
+            refresh,
+            #[strong]
+            watches,
+            move |event: &Event| {
+                watches.watch(event, |event| {
+                    vec![event.connect_timeframe_notify(clone!(
+                        #[strong]
+                        refresh,
+                        move |_| refresh()
+                    ))]
+                });
+            }
+        );
+        for event in self.events().iter::<Event>() {
+            watch_event(&event.expect("Model should not be mutated during iteration"));
+        }
+        watches.watch(self, |obj| {
+            vec![obj.connect_events_changed(clone!(
+                #[strong]
+                refresh,
+                #[strong]
+                watches,
+                move |_, added, removed| {
+                    for event in removed {
+                        watches.unwatch(event);
+                    }
+                    for event in added {
+                        watch_event(event);
+                    }
+                    refresh();
+                }
+            ))]
+        });
+
+        refresh();
+        days.upcast()
+    }
+
     /// Ask the backend to create a new event in this calendar.
-    pub fn create_event(&self, name: &str, description: &str) {
+    ///
+    /// The URI of the new event is returned, and the event appears in the calendar once the
+    /// backend reports its creation.
+    /// Read-only calendars fail with [`WriteError::ReadOnly`].
+    pub fn create_event(&self, new_event: &NewEvent) -> Result<String, WriteError> {
+        if self.read_only() {
+            return Err(WriteError::ReadOnly);
+        }
+
         // TODO: dispatch to relevant provider instead
-        self.manager().create_event(&self.uri(), name, description);
+        self.writable_manager()?
+            .create_event(&self.uri(), new_event)
+    }
+
+    /// Ask the backend to create a new event in this calendar asynchronously.
+    ///
+    /// This behaves like [`Calendar::create_event`], except that transient failures are retried
+    /// following [`Manager::set_write_retry`].
+    pub async fn create_event_future(&self, new_event: &NewEvent) -> Result<String, WriteError> {
+        if self.read_only() {
+            return Err(WriteError::ReadOnly);
+        }
+
+        // TODO: dispatch to relevant provider instead
+        self.writable_manager()?
+            .create_event_future(&self.uri(), new_event)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::{Date, Weekday, date};
+
+    use super::*;
+    use crate::{Instant, Provider, Timeframe};
+
+    fn calendar() -> Calendar {
+        let provider = Provider::new_detached("urn:provider", "Provider");
+        let collection = Collection::new_detached(&provider, "urn:collection", "Collection");
+        Calendar::new_detached(&collection, "urn:calendar", "Calendar", RGBA::BLUE)
+    }
+
+    fn all_day(start: Date, end: Date) -> Timeframe {
+        Timeframe::from_instants(Instant::Date(start), Instant::Date(end)).unwrap()
+    }
+
+    fn add_event(calendar: &Calendar, uri: &str, timeframe: &Timeframe) -> Event {
+        let event = Event::new_detached(calendar, uri, uri, "", timeframe);
+        calendar.add_event(&event);
+        event
+    }
+
+    #[test]
+    fn events_are_sorted_by_start() {
+        let calendar = calendar();
+        let later = add_event(
+            &calendar,
+            "urn:later",
+            &all_day(date(2024, 6, 3), date(2024, 6, 4)),
+        );
+        let earlier = add_event(
+            &calendar,
+            "urn:earlier",
+            &all_day(date(2024, 6, 1), date(2024, 6, 2)),
+        );
+
+        assert_eq!(calendar.events_sorted_vec(), vec![earlier, later]);
+    }
+
+    #[test]
+    fn events_by_week_repeats_events_spanning_weeks() {
+        let calendar = calendar();
+        // From Saturday to Monday, both included
+        let weekend = add_event(
+            &calendar,
+            "urn:weekend",
+            &all_day(date(2024, 6, 8), date(2024, 6, 11)),
+        );
+        let timeframe = weekend.timeframe().unwrap();
+        assert_eq!(timeframe.day_count(), 3);
+
+        let weeks = calendar.events_by_week(date(2024, 6, 5), 3, Weekday::Monday);
+        assert_eq!(
+            weeks,
+            vec![
+                (date(2024, 6, 3), vec![weekend.clone()]),
+                (date(2024, 6, 10), vec![weekend]),
+                (date(2024, 6, 17), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn detached_calendar_rejects_writes() {
+        let calendar = calendar();
+        let new_event = NewEvent {
+            name: "Event".to_string(),
+            description: String::new(),
+            timeframe: all_day(date(2024, 6, 1), date(2024, 6, 2)),
+            location: String::new(),
+        };
+
+        assert!(matches!(
+            calendar.create_event(&new_event),
+            Err(WriteError::Detached)
+        ));
     }
 }
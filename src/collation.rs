@@ -0,0 +1,33 @@
+use gdk::glib;
+
+/// The key to sort resource names with, for the current locale.
+///
+/// Names are compared case-insensitively and following the collation rules of the locale, so
+/// accented names sort next to their unaccented counterparts.
+pub fn name_collation_key(name: &str) -> glib::CollationKey {
+    glib::CollationKey::from(glib::casefold(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use gettextrs::{LocaleCategory, setlocale};
+
+    use super::*;
+
+    #[test]
+    fn names_differing_by_case_are_equal() {
+        assert_eq!(name_collation_key("apple"), name_collation_key("Apple"));
+        assert!(name_collation_key("Apple") < name_collation_key("banana"));
+    }
+
+    #[test]
+    #[ignore = "sets the locale of the whole process, run alone with --ignored --test-threads=1"]
+    fn accented_names_sort_with_unaccented_ones() {
+        // Codepoint order puts "é" after "z", so this needs a real locale
+        setlocale(LocaleCategory::LcCollate, "en_US.UTF-8")
+            .expect("The en_US.UTF-8 locale should be available");
+
+        assert!(name_collation_key("école") < name_collation_key("Zèbre"));
+        assert!(name_collation_key("Ève") < name_collation_key("Fabrice"));
+    }
+}
@@ -1,14 +1,36 @@
-use std::cell::{OnceCell, RefCell};
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    cmp::Ordering,
+    rc::Rc,
+    sync::LazyLock,
+};
 
 use gdk::{
-    RGBA,
     gio::{self, ListStore},
-    glib::{self, Object, clone},
+    glib::{self, Object, clone, closure_local, subclass::Signal},
     prelude::*,
     subclass::prelude::*,
 };
 
-use crate::{Calendar, Manager, Provider};
+use tracing::{info, warn};
+
+use crate::{
+    Calendar, Event, Manager, NewCalendar, Provider, Timeframe, ValidationError, WriteError, Zoned,
+    name_collation_key, utils::SignalWatches,
+};
+
+/// Compare calendars by their position set by the user, then by name.
+fn compare_calendars(a: &glib::Object, b: &glib::Object) -> Ordering {
+    let a = a
+        .downcast_ref::<Calendar>()
+        .expect("Item should be a calendar");
+    let b = b
+        .downcast_ref::<Calendar>()
+        .expect("Item should be a calendar");
+    a.sort_order()
+        .cmp(&b.sort_order())
+        .then_with(|| name_collation_key(&a.name()).cmp(&name_collation_key(&b.name())))
+}
 
 mod imp {
     use super::*;
@@ -16,8 +38,9 @@ mod imp {
     #[derive(Debug, Default, glib::Properties)]
     #[properties(wrapper_type = super::Collection)]
     pub struct Collection {
-        #[property(get, construct_only)]
-        manager: OnceCell<Manager>,
+        /// The manager of this collection, `None` for detached collections.
+        #[property(get, construct_only, nullable)]
+        manager: RefCell<Option<Manager>>,
         #[property(get, construct_only)]
         provider: OnceCell<Provider>,
         #[property(get, construct_only)]
@@ -26,6 +49,13 @@ mod imp {
         name: RefCell<String>,
         #[property(get)]
         calendars: OnceCell<ListStore>,
+        /// Whether the calendars of this collection were loaded, see
+        /// [`Collection::ensure_calendars_loaded`](super::Collection::ensure_calendars_loaded).
+        #[property(get)]
+        pub(super) calendars_loaded: Cell<bool>,
+        /// The handlers connected to the calendars, to disconnect when they leave this
+        /// collection.
+        pub(super) calendar_watches: SignalWatches,
     }
 
     #[glib::object_subclass]
@@ -43,6 +73,21 @@ mod imp {
 
             self.calendars.get_or_init(ListStore::new::<Calendar>);
         }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
+                vec![
+                    Signal::builder("deleted").build(),
+                    Signal::builder("calendar-added")
+                        .param_types([Calendar::static_type()])
+                        .build(),
+                    Signal::builder("calendar-removed")
+                        .param_types([Calendar::static_type()])
+                        .build(),
+                ]
+            });
+            SIGNALS.as_ref()
+        }
     }
 
     impl ListModelImpl for Collection {
@@ -82,26 +127,308 @@ impl Collection {
             .build()
     }
 
-    /// Add a calendar to this collection.
+    /// Create a collection of `provider` without a manager, for tests.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn new_detached(provider: &Provider, uri: &str, name: &str) -> Self {
+        glib::Object::builder()
+            .property("provider", provider)
+            .property("uri", uri)
+            .property("name", name)
+            .build()
+    }
+
+    /// Signal that this collection was deleted.
+    pub(super) fn emit_deleted(&self) {
+        let calendars = self.calendars().iter::<Calendar>().collect::<Vec<_>>();
+        for calendar in calendars {
+            calendar
+                .expect("Model should not be mutated during iteration")
+                .emit_deleted();
+        }
+
+        self.emit_by_name::<()>("deleted", &[]);
+    }
+
+    /// Connect to the signal emitted when this collection is deleted.
+    pub fn connect_deleted<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "deleted",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    /// Load the calendars of this collection and their events, if they were not loaded yet.
+    ///
+    /// Calendars are loaded along with the manager, unless it was created with
+    /// [`Manager::with_lazy_calendars`]. In that case, this must be called before showing the
+    /// calendars of this collection, for instance when it is first expanded.
+    pub fn ensure_calendars_loaded(&self) {
+        if self.calendars_loaded() {
+            return;
+        }
+
+        match self.manager() {
+            Some(manager) => manager.load_collection_calendars(self),
+            // Detached collections have nothing to load
+            None => self.set_calendars_loaded(),
+        }
+    }
+
+    /// Mark the calendars of this collection as loaded.
+    pub(crate) fn set_calendars_loaded(&self) {
+        if !self.imp().calendars_loaded.replace(true) {
+            self.notify_calendars_loaded();
+        }
+    }
+
+    /// Add a calendar to this collection, keeping the calendars sorted.
     pub(crate) fn add_calendar(&self, calendar: &Calendar) {
-        self.imp().calendars().append(calendar);
+        self.imp()
+            .calendars()
+            .insert_sorted(calendar, compare_calendars);
+        self.emit_by_name::<()>("calendar-added", &[calendar]);
 
-        calendar.connect_deleted(clone!(
+        self.imp().calendar_watches.watch(calendar, |calendar| {
+            let resort = clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_: &Calendar| {
+                    obj.imp().calendars().sort(compare_calendars);
+                }
+            );
+
+            vec![
+                calendar.connect_sort_order_notify(resort.clone()),
+                calendar.connect_name_notify(resort),
+                calendar.connect_deleted(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    move |calendar| {
+                        obj.remove_calendar(calendar);
+                    }
+                )),
+            ]
+        });
+    }
+
+    /// Apply the name retrieved from the backend, notifying if it changed.
+    pub(crate) fn emit_updated(&self, name: &str) {
+        if name != self.name() {
+            self.set_name(name);
+            info!("Collection {} updated to name {name}", self.uri());
+        }
+    }
+
+    /// Remove a calendar that was deleted or moved to another collection.
+    pub(crate) fn remove_calendar(&self, calendar: &Calendar) {
+        self.imp().calendar_watches.unwatch(calendar);
+
+        // The calendar may have been moved to another collection since it was added
+        if let Some(index) = self.calendars().find(calendar) {
+            self.calendars().remove(index);
+            self.emit_by_name::<()>("calendar-removed", &[calendar]);
+        }
+    }
+
+    /// Connect to the signal emitted when a calendar is added to this collection.
+    pub fn connect_calendar_added<F: Fn(&Self, &Calendar) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "calendar-added",
+            true,
+            closure_local!(|obj: Self, calendar: Calendar| {
+                f(&obj, &calendar);
+            }),
+        )
+    }
+
+    /// Connect to the signal emitted when a calendar is removed from this collection.
+    pub fn connect_calendar_removed<F: Fn(&Self, &Calendar) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "calendar-removed",
+            true,
+            closure_local!(|obj: Self, calendar: Calendar| {
+                f(&obj, &calendar);
+            }),
+        )
+    }
+
+    /// Retrieve the events of every calendar of this collection overlapping the range from
+    /// `start` to `end`, sorted by start.
+    ///
+    /// The model is kept up to date as events are added to or removed from the calendars, and as
+    /// calendars are added to or removed from this collection, until it is dropped.
+    pub fn events_in_range(&self, start: &Zoned, end: &Zoned) -> gio::ListModel {
+        let range = Timeframe::new(false, start.clone(), end.clone());
+        let events = ListStore::new::<Event>();
+
+        let watches = Rc::new(SignalWatches::default());
+        let events_weak = events.downgrade();
+        let refresh = Rc::new(clone!(
             #[weak(rename_to = obj)]
             self,
-            move |calendar| {
-                let index = obj
+            #[strong]
+            watches,
+            move || {
+                let Some(events) = events_weak.upgrade() else {
+                    watches.clear();
+                    return;
+                };
+
+                let mut matching = obj
                     .calendars()
-                    .find(calendar)
-                    .expect("Calendar should be found");
-                obj.calendars().remove(index);
+                    .iter::<Calendar>()
+                    .map(|calendar| calendar.expect("Model should not be mutated during iteration"))
+                    .flat_map(|calendar| calendar.events_sorted_vec())
+                    .filter(|event| {
+                        event
+                            .timeframe()
+                            .is_some_and(|timeframe| timeframe.overlaps(&range))
+                    })
+                    .collect::<Vec<_>>();
+                matching.sort_by_cached_key(|event| {
+                    event
+                        .timeframe()
+                        .map(|timeframe| timeframe.start().0.timestamp())
+                });
+                events.splice(0, events.n_items(), &matching);
             }
         ));
+
+        let watch_calendar = clone!(
+            #[strong]
+            refresh,
+            #[strong]
+            watches,
+            move |calendar: &Calendar| {
+                watches.watch(&calendar.events(), |calendar_events| {
+                    vec![calendar_events.connect_items_changed(clone!(
+                        #[strong]
+                        refresh,
+                        move |_, _, _, _| refresh()
+                    ))]
+                });
+            }
+        );
+        for calendar in self.calendars().iter::<Calendar>() {
+            watch_calendar(&calendar.expect("Model should not be mutated during iteration"));
+        }
+        watches.watch(self, |obj| {
+            vec![
+                obj.connect_calendar_added(clone!(
+                    #[strong]
+                    refresh,
+                    move |_, calendar| {
+                        watch_calendar(calendar);
+                        refresh();
+                    }
+                )),
+                obj.connect_calendar_removed(clone!(
+                    #[strong]
+                    refresh,
+                    #[strong]
+                    watches,
+                    move |_, calendar| {
+                        watches.unwatch(&calendar.events());
+                        refresh();
+                    }
+                )),
+            ]
+        });
+
+        refresh();
+        events.upcast()
+    }
+
+    /// Ask the backend to subscribe to a remote iCalendar feed, as a new calendar in this
+    /// collection.
+    ///
+    /// The backend fetches the feed again every `refresh`. The URI of the new calendar is
+    /// returned, and the calendar appears in the collection, read-only, once the backend reports
+    /// its creation.
+    pub fn subscribe_ical(&self, url: &str, refresh: jiff::Span) -> Result<String, WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .ok_or(WriteError::Detached)?
+            .subscribe_ical(&self.uri(), url, refresh)
+    }
+
+    /// Ask the backend to store the given order of the calendars of this collection.
+    ///
+    /// `order` lists calendar URIs. Duplicates and URIs of calendars outside of this collection
+    /// are ignored, and the calendars missing from it keep their relative order after the listed
+    /// ones. Only the calendars whose position changes are written, in a single call.
+    ///
+    /// Backends that cannot update several positions at once get one call per calendar, so a
+    /// failure may leave the order partly applied.
+    pub fn reorder_calendars(&self, order: &[&str]) -> Result<(), WriteError> {
+        let manager = self.manager().ok_or(WriteError::Detached)?;
+
+        let calendars = self
+            .imp()
+            .calendars()
+            .iter::<Calendar>()
+            .map(|calendar| calendar.expect("Model should not be mutated during iteration"))
+            .collect::<Vec<_>>();
+
+        let mut ordered: Vec<&Calendar> = Vec::with_capacity(calendars.len());
+        for uri in order {
+            match calendars.iter().find(|calendar| calendar.uri() == *uri) {
+                Some(calendar) if !ordered.contains(&calendar) => ordered.push(calendar),
+                Some(_) => warn!("Calendar {uri} is listed twice in the new order"),
+                None => warn!("Calendar {uri} is not in collection {}", self.uri()),
+            }
+        }
+        for calendar in &calendars {
+            if !ordered.contains(&calendar) {
+                ordered.push(calendar);
+            }
+        }
+
+        let sort_orders = (1..)
+            .zip(ordered)
+            .filter(|(sort_order, calendar)| calendar.sort_order() != *sort_order)
+            .map(|(sort_order, calendar)| (calendar.uri(), sort_order))
+            .collect::<Vec<_>>();
+
+        // TODO: dispatch to relevant provider instead
+        manager.update_calendar_sort_orders(&sort_orders)
     }
 
     /// Ask the backend to create a new calendar in this collection.
-    pub fn create_calendar(&self, name: &str, color: RGBA) {
+    ///
+    /// The URI of the new calendar is returned, and the calendar appears in the collection once
+    /// the backend reports its creation.
+    pub fn create_calendar(&self, new_calendar: &NewCalendar) -> Result<String, WriteError> {
         // TODO: dispatch to relevant provider instead
-        self.manager().create_calendar(&self.uri(), name, color);
+        self.manager()
+            .ok_or(WriteError::Detached)?
+            .create_calendar(&self.uri(), new_calendar)
+    }
+
+    /// Ask the backend to create a new calendar in this collection, with a color given as CSS
+    /// color text, such as `#3584e4`, `rgb(53, 132, 228)` or `teal`.
+    ///
+    /// Invalid colors fail with [`ValidationError::InvalidColor`], before anything is written.
+    pub fn create_calendar_named_color(
+        &self,
+        name: &str,
+        color: &str,
+    ) -> Result<String, WriteError> {
+        let color = gdk::RGBA::parse(color.trim())
+            .map_err(|_| ValidationError::InvalidColor(color.to_string()))?;
+
+        self.create_calendar(&NewCalendar {
+            name: name.to_string(),
+            color,
+        })
     }
 }
@@ -1,6 +1,11 @@
 use std::cell::RefCell;
 
-use gdk::{gio, glib, prelude::*, subclass::prelude::*};
+use gdk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
 
 use super::collection::Collection;
 
@@ -55,6 +60,21 @@ impl CollectionsModel {
         };
         self.items_changed(pos, 0, 1);
 
+        collection.connect_deleted(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |collection| {
+                let pos = obj
+                    .imp()
+                    .0
+                    .borrow()
+                    .iter()
+                    .position(|c| c == collection)
+                    .expect("Collection should be found");
+                obj.remove(pos as u32);
+            }
+        ));
+
         // collection.connect_items_changed(clone!(
         //     #[weak(rename_to = obj)]
         //     self,
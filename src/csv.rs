@@ -0,0 +1,102 @@
+use crate::{Calendar, Event, Zoned};
+
+/// The header row, naming the columns of the other rows.
+const HEADER: [&str; 6] = ["Name", "Description", "Start", "End", "All day", "Location"];
+
+/// Write the events of a calendar as CSV, following RFC 4180.
+///
+/// Events are sorted by start, one per row after the header row. Times are ISO 8601, as dates
+/// for all-day events and as datetimes with their offset otherwise. Locations are not loaded
+/// from the database, so that column is left empty.
+pub(crate) fn write_calendar(calendar: &Calendar) -> String {
+    let mut csv = String::new();
+    write_row(&mut csv, &HEADER);
+    for event in calendar.events_sorted_vec() {
+        write_event(&mut csv, &event);
+    }
+    csv
+}
+
+/// Write an event as a row.
+fn write_event(csv: &mut String, event: &Event) {
+    let (start, end, all_day) = match event.timeframe() {
+        Some(timeframe) if timeframe.all_day() => (
+            timeframe.start().date().to_string(),
+            timeframe.end().date().to_string(),
+            "true",
+        ),
+        Some(timeframe) => (
+            format_datetime(&timeframe.start()),
+            format_datetime(&timeframe.end()),
+            "false",
+        ),
+        None => (String::new(), String::new(), ""),
+    };
+
+    write_row(
+        csv,
+        &[
+            &event.name(),
+            &event.description(),
+            &start,
+            &end,
+            all_day,
+            "",
+        ],
+    );
+}
+
+/// Format a datetime as ISO 8601 with its offset, such as `2024-06-01T09:00:00-04:00`.
+fn format_datetime(zoned: &Zoned) -> String {
+    zoned.0.strftime("%Y-%m-%dT%H:%M:%S%:z").to_string()
+}
+
+/// Write a row of fields, ending with a line break.
+fn write_row(csv: &mut String, fields: &[&str]) {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            csv.push(',');
+        }
+        csv.push_str(&escape_field(field));
+    }
+    csv.push_str("\r\n");
+}
+
+/// Quote a field if it contains a separator, a quote or a line break, doubling its quotes.
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_not_quoted() {
+        assert_eq!(escape_field("Team meeting"), "Team meeting");
+        assert_eq!(escape_field(""), "");
+    }
+
+    #[test]
+    fn fields_with_separators_or_quotes_are_quoted() {
+        assert_eq!(escape_field("Lunch, then walk"), "\"Lunch, then walk\"");
+        assert_eq!(escape_field("The \"big\" one"), "\"The \"\"big\"\" one\"");
+        assert_eq!(escape_field("Line\r\nbreak"), "\"Line\r\nbreak\"");
+        assert_eq!(escape_field("Line\nbreak"), "\"Line\nbreak\"");
+    }
+
+    #[test]
+    fn rows_end_with_crlf() {
+        let mut csv = String::new();
+        write_row(&mut csv, &["a,b", "say \"hi\"", "one\r\ntwo", "plain"]);
+        write_row(&mut csv, &["", ""]);
+        assert_eq!(
+            csv,
+            "\"a,b\",\"say \"\"hi\"\"\",\"one\r\ntwo\",plain\r\n,\r\n"
+        );
+    }
+}
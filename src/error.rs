@@ -0,0 +1,187 @@
+use std::fmt;
+
+use gdk::glib;
+
+/// A reason a value was rejected before being sent to a backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The name is empty, or only made of whitespace.
+    EmptyName,
+    /// The name has more characters than the maximum length of the manager.
+    NameTooLong { length: usize, max_length: usize },
+    /// The name contains control characters, such as line breaks.
+    ControlCharacters,
+    /// The color is not a valid CSS color, such as `#3584e4` or `teal`.
+    InvalidColor(String),
+}
+
+impl ValidationError {
+    /// Check that a name can be written, with at most `max_length` characters.
+    pub(crate) fn check_name(name: &str, max_length: usize) -> Result<(), Self> {
+        if name.trim().is_empty() {
+            return Err(Self::EmptyName);
+        }
+        if name.chars().any(char::is_control) {
+            return Err(Self::ControlCharacters);
+        }
+        let length = name.chars().count();
+        if length > max_length {
+            return Err(Self::NameTooLong { length, max_length });
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyName => write!(f, "the name is empty"),
+            Self::NameTooLong { length, max_length } => write!(
+                f,
+                "the name has {length} characters, more than the maximum of {max_length}"
+            ),
+            Self::ControlCharacters => write!(f, "the name contains control characters"),
+            Self::InvalidColor(color) => write!(f, "\"{color}\" is not a valid color"),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// An error returned when reading a resource from the database.
+#[derive(Debug)]
+pub enum PreResourceError {
+    /// The query to the database failed.
+    Query(glib::Error),
+    /// The resource exists but could not be read, for instance because it lacks a required
+    /// property or has an invalid value.
+    Unreadable,
+}
+
+impl fmt::Display for PreResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Query(err) => write!(f, "query failed: {err}"),
+            Self::Unreadable => write!(f, "the resource could not be read"),
+        }
+    }
+}
+
+impl std::error::Error for PreResourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Query(err) => Some(err),
+            Self::Unreadable => None,
+        }
+    }
+}
+
+impl From<glib::Error> for PreResourceError {
+    fn from(err: glib::Error) -> Self {
+        Self::Query(err)
+    }
+}
+
+/// An error returned when asking a backend to write a resource.
+#[derive(Debug)]
+pub enum WriteError {
+    /// The manager was opened read-only, or the resource cannot be written to.
+    ReadOnly,
+    /// The resource is not attached to a manager, so there is no backend to write to.
+    Detached,
+    /// The backend does not support this write.
+    Unsupported,
+    /// The write is made on behalf of the user, but the manager has no user email.
+    NoUserEmail,
+    /// A value was rejected before being sent to the backend.
+    Invalid(ValidationError),
+    /// The resource was changed or deleted by another client since it was last read.
+    Conflict,
+    /// The DBus call to the write service failed.
+    DBus(glib::Error),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadOnly => write!(f, "the manager is read-only"),
+            Self::Detached => write!(f, "the resource is not attached to a manager"),
+            Self::Unsupported => write!(f, "the operation is not supported"),
+            Self::NoUserEmail => write!(f, "no user email is set"),
+            Self::Invalid(err) => write!(f, "invalid value: {err}"),
+            Self::Conflict => write!(f, "the resource was changed by another client"),
+            Self::DBus(err) => write!(f, "DBus call failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ReadOnly
+            | Self::Detached
+            | Self::Unsupported
+            | Self::NoUserEmail
+            | Self::Conflict => None,
+            Self::Invalid(err) => Some(err),
+            Self::DBus(err) => Some(err),
+        }
+    }
+}
+
+impl From<ValidationError> for WriteError {
+    fn from(err: ValidationError) -> Self {
+        Self::Invalid(err)
+    }
+}
+
+impl From<glib::Error> for WriteError {
+    fn from(err: glib::Error) -> Self {
+        Self::DBus(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_names_are_rejected() {
+        assert_eq!(
+            ValidationError::check_name("", 10),
+            Err(ValidationError::EmptyName)
+        );
+        assert_eq!(
+            ValidationError::check_name(" \t ", 10),
+            Err(ValidationError::EmptyName)
+        );
+    }
+
+    #[test]
+    fn names_are_limited_in_characters() {
+        assert_eq!(
+            ValidationError::check_name("Calendars", 5),
+            Err(ValidationError::NameTooLong {
+                length: 9,
+                max_length: 5
+            })
+        );
+        // Characters are counted rather than bytes
+        assert_eq!(ValidationError::check_name("Écoles", 6), Ok(()));
+    }
+
+    #[test]
+    fn control_characters_are_rejected() {
+        assert_eq!(
+            ValidationError::check_name("Work\nHome", 20),
+            Err(ValidationError::ControlCharacters)
+        );
+    }
+
+    #[test]
+    fn valid_names_are_accepted() {
+        assert_eq!(ValidationError::check_name("Work", 4), Ok(()));
+        assert_eq!(ValidationError::check_name(" Home ", 20), Ok(()));
+    }
+}
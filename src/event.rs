@@ -1,15 +1,97 @@
 use std::{
-    cell::{OnceCell, RefCell},
+    cell::{Cell, OnceCell, RefCell},
+    marker::PhantomData,
     sync::LazyLock,
 };
 
 use gdk::{
-    glib::{self, Object, closure_local, subclass::Signal},
+    RGBA,
+    glib::{self, Object, clone, closure_local, subclass::Signal},
     prelude::*,
     subclass::prelude::*,
 };
+use gettextrs::gettext;
+use tracing::{info, warn};
 
-use crate::{Calendar, Manager, Timeframe};
+use crate::{
+    Calendar, DeletedEvent, Manager, NewEvent, Occurrence, RecurrenceFilter, RecurrenceRule,
+    Timeframe, UndoAction, WriteError, Zoned, pre_resource::PreEvent,
+};
+
+/// Whether an event blocks time in the schedule of its attendees.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "EventTransparency")]
+pub enum EventTransparency {
+    /// The event makes its attendees busy.
+    #[default]
+    Opaque,
+    /// The event leaves its attendees free, for instance a reminder or a holiday.
+    Transparent,
+}
+
+impl EventTransparency {
+    /// Parse the transparency as stored in the database, defaulting to opaque.
+    pub(crate) fn from_db(value: Option<&str>) -> Self {
+        match value {
+            Some(value) if value.eq_ignore_ascii_case("transparent") => Self::Transparent,
+            _ => Self::Opaque,
+        }
+    }
+
+    /// The transparency in the form it is stored in the database.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Opaque => "opaque",
+            Self::Transparent => "transparent",
+        }
+    }
+}
+
+/// The response of an attendee to an event invitation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "ParticipationStatus")]
+pub enum ParticipationStatus {
+    /// The attendee did not respond yet.
+    #[default]
+    NeedsAction,
+    Accepted,
+    Declined,
+    Tentative,
+    /// The attendee delegated the invitation to someone else.
+    Delegated,
+}
+
+impl ParticipationStatus {
+    /// Parse the participation status as stored in the database, defaulting to
+    /// [`ParticipationStatus::NeedsAction`].
+    pub(crate) fn from_db(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "accepted" => Self::Accepted,
+            "declined" => Self::Declined,
+            "tentative" => Self::Tentative,
+            "delegated" => Self::Delegated,
+            _ => Self::NeedsAction,
+        }
+    }
+
+    /// The participation status in the form it is stored in the database.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::NeedsAction => "needs-action",
+            Self::Accepted => "accepted",
+            Self::Declined => "declined",
+            Self::Tentative => "tentative",
+            Self::Delegated => "delegated",
+        }
+    }
+}
+
+/// Someone invited to an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attendee {
+    pub email: String,
+    pub status: ParticipationStatus,
+}
 
 mod imp {
 
@@ -18,8 +100,9 @@ mod imp {
     #[derive(Debug, Default, glib::Properties)]
     #[properties(wrapper_type = super::Event)]
     pub struct Event {
-        #[property(get, construct_only)]
-        manager: OnceCell<Manager>,
+        /// The manager of this event, `None` for detached events.
+        #[property(get, construct_only, nullable)]
+        manager: RefCell<Option<Manager>>,
         #[property(get, construct_only)]
         calendar: OnceCell<Calendar>,
         #[property(get, construct_only)]
@@ -30,6 +113,26 @@ mod imp {
         description: RefCell<String>,
         #[property(get, set)]
         timeframe: RefCell<Option<Timeframe>>,
+        /// The color overriding the one of the calendar, if any.
+        #[property(get)]
+        pub(super) color: RefCell<Option<RGBA>>,
+        #[property(get, builder(EventTransparency::default()))]
+        pub(super) transparency: Cell<EventTransparency>,
+        /// The color of the calendar of this event, updated when the calendar changes color.
+        #[property(get = Self::calendar_color, type = RGBA)]
+        calendar_color: PhantomData<RGBA>,
+        pub(super) calendar_color_handler: RefCell<Option<glib::SignalHandlerId>>,
+        /// The occurrences excluded from the recurrence of this event, sorted.
+        pub(super) exdates: RefCell<Vec<Zoned>>,
+        pub(super) attendees: RefCell<Vec<Attendee>>,
+        pub(super) recurrence: RefCell<Option<RecurrenceRule>>,
+        /// The URI of the recurring event this event overrides an occurrence of.
+        pub(super) recurrence_of: RefCell<Option<String>>,
+        /// The start of the occurrence this event overrides.
+        pub(super) recurrence_id: RefCell<Option<Zoned>>,
+        /// The time the reminder was snoozed to, if it was snoozed.
+        pub(super) snoozed_reminder: RefCell<Option<Zoned>>,
+        pub(super) reminder_dismissed: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -39,8 +142,26 @@ mod imp {
         type ParentType = Object;
     }
 
+    impl Event {
+        fn calendar_color(&self) -> RGBA {
+            self.obj().calendar().color().unwrap_or(RGBA::BLACK)
+        }
+    }
+
     #[glib::derived_properties]
     impl ObjectImpl for Event {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let handler = obj.calendar().connect_color_notify(clone!(
+                #[weak]
+                obj,
+                move |_| obj.notify_calendar_color()
+            ));
+            self.calendar_color_handler.replace(Some(handler));
+        }
+
         fn signals() -> &'static [Signal] {
             static SIGNALS: LazyLock<Vec<Signal>> =
                 LazyLock::new(|| vec![Signal::builder("deleted").build()]);
@@ -54,9 +175,37 @@ glib::wrapper! {
 }
 
 impl Event {
-    /// Create a new event from its properties.
-    pub(crate) fn new(
-        manager: &Manager,
+    /// Create a new event from the properties retrieved from the database.
+    pub(crate) fn new(manager: &Manager, calendar: &Calendar, pre_event: &PreEvent) -> Self {
+        let event: Self = glib::Object::builder()
+            .property("manager", manager)
+            .property("calendar", calendar)
+            .property("uri", &pre_event.uri)
+            .property("name", &pre_event.name)
+            .property("description", &pre_event.description)
+            .property("timeframe", pre_event.timeframe())
+            .build();
+        event.imp().color.replace(pre_event.color);
+        event.imp().transparency.set(pre_event.transparency);
+        event.imp().exdates.replace(pre_event.exdates.clone());
+        event.imp().attendees.replace(pre_event.attendees.clone());
+        event.imp().recurrence.replace(pre_event.recurrence.clone());
+        event
+            .imp()
+            .recurrence_of
+            .replace(pre_event.recurrence_of.clone());
+        event
+            .imp()
+            .recurrence_id
+            .replace(pre_event.recurrence_id.clone());
+        event
+    }
+
+    /// Create an event of `calendar` without a manager, for tests.
+    ///
+    /// It is not added to the calendar, and its writes fail with [`WriteError::Detached`].
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn new_detached(
         calendar: &Calendar,
         uri: &str,
         name: &str,
@@ -64,7 +213,6 @@ impl Event {
         timeframe: &Timeframe,
     ) -> Self {
         glib::Object::builder()
-            .property("manager", manager)
             .property("calendar", calendar)
             .property("uri", uri)
             .property("name", name)
@@ -73,8 +221,423 @@ impl Event {
             .build()
     }
 
+    /// The color to display this event with.
+    ///
+    /// This is the color of the event if it overrides it, or the color of its calendar.
+    pub fn effective_color(&self) -> RGBA {
+        self.color()
+            .or_else(|| self.calendar().color())
+            .unwrap_or(RGBA::BLACK)
+    }
+
+    /// Ask the backend to override the color of this event, or to inherit the color of its
+    /// calendar again with `None`.
+    pub fn set_color(&self, color: Option<RGBA>) -> Result<(), WriteError> {
+        let old_color = self.color();
+
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .ok_or(WriteError::Detached)?
+            .update_event_color(&self.uri(), color)?;
+
+        self.manager()
+            .ok_or(WriteError::Detached)?
+            .record_undo(UndoAction::SetEventColor {
+                uri: self.uri(),
+                old_color,
+                new_color: color,
+            });
+        Ok(())
+    }
+
+    /// Ask the backend to change the color override of this event asynchronously, retrying
+    /// transient failures.
+    ///
+    /// See [`Event::set_color`].
+    pub async fn set_color_future(&self, color: Option<RGBA>) -> Result<(), WriteError> {
+        let old_color = self.color();
+
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .ok_or(WriteError::Detached)?
+            .update_event_color_future(&self.uri(), color)
+            .await?;
+
+        self.manager()
+            .ok_or(WriteError::Detached)?
+            .record_undo(UndoAction::SetEventColor {
+                uri: self.uri(),
+                old_color,
+                new_color: color,
+            });
+        Ok(())
+    }
+
+    /// The email address identifying the user among the attendees of this event.
+    ///
+    /// This is [`Manager::user_email`] if it is set, or the account identity of the provider of
+    /// this event otherwise.
+    pub fn user_email(&self) -> Option<String> {
+        self.manager()
+            .and_then(|manager| manager.user_email())
+            .or_else(|| self.calendar().collection().provider().account_identity())
+    }
+
+    /// Ask the backend to delete this event.
+    ///
+    /// Undoing the deletion creates the event again from its fields, under a new URI.
+    pub fn delete(&self) -> Result<(), WriteError> {
+        let manager = self.manager().ok_or(WriteError::Detached)?;
+        // The fields are captured first, as the event is dropped once the backend reports its
+        // deletion
+        let deleted_event = DeletedEvent::from_event(self);
+
+        // TODO: dispatch to relevant provider instead
+        manager.delete_event(&self.uri())?;
+
+        manager.record_undo(UndoAction::DeleteEvent {
+            event: deleted_event,
+        });
+        Ok(())
+    }
+
+    /// Ask the backend to delete this event asynchronously, retrying transient failures.
+    ///
+    /// See [`Event::delete`].
+    pub async fn delete_future(&self) -> Result<(), WriteError> {
+        let manager = self.manager().ok_or(WriteError::Detached)?;
+        let deleted_event = DeletedEvent::from_event(self);
+
+        // TODO: dispatch to relevant provider instead
+        manager.delete_event_future(&self.uri()).await?;
+
+        manager.record_undo(UndoAction::DeleteEvent {
+            event: deleted_event,
+        });
+        Ok(())
+    }
+
+    /// Ask the backend to create a copy of this event, in `into` or else in the calendar of this
+    /// event.
+    ///
+    /// The copy has the same description and time frame, and the name of this event followed by
+    /// a translatable " (copy)". Locations are not loaded from the database, so the copy has
+    /// none. The URI of the copy is returned, and it appears in its calendar once the backend
+    /// reports its creation.
+    pub fn duplicate(&self, into: Option<&Calendar>) -> Result<String, WriteError> {
+        let calendar = into.cloned().unwrap_or_else(|| self.calendar());
+
+        calendar.create_event(&NewEvent {
+            name: gettext("{} (copy)").replace("{}", &self.name()),
+            description: self.description(),
+            timeframe: self.timeframe().unwrap_or_default(),
+            location: String::new(),
+        })
+    }
+
+    /// Ask the backend to record the response of the user to this event.
+    ///
+    /// The user is identified by [`Event::user_email`], and the write fails with
+    /// [`WriteError::NoUserEmail`] if there is none. The attendees of this event are updated once
+    /// the backend reports the change.
+    pub fn set_my_response(&self, response: ParticipationStatus) -> Result<(), WriteError> {
+        let manager = self.manager().ok_or(WriteError::Detached)?;
+        let email = self.user_email().ok_or(WriteError::NoUserEmail)?;
+
+        // TODO: dispatch to relevant provider instead
+        manager.update_event_participation(&self.uri(), &email, response)
+    }
+
+    /// Postpone the reminder of this event.
+    ///
+    /// The reminder fires at the start of the event, and each snooze postpones it from its current
+    /// time. This is transient state: the event itself is left unchanged, and snoozes are lost
+    /// when the manager is dropped.
+    pub fn snooze_reminder(&self, by: jiff::Span) {
+        let next_reminder = self.next_reminder();
+        let Ok(snoozed) = next_reminder.0.checked_add(by) else {
+            warn!("Failed to snooze reminder of event {} by {by}", self.uri());
+            return;
+        };
+
+        self.imp().snoozed_reminder.replace(Some(Zoned(snoozed)));
+        self.imp().reminder_dismissed.set(false);
+    }
+
+    /// Stop reminding about this event, until it is snoozed again.
+    pub fn dismiss_reminder(&self) {
+        self.imp().reminder_dismissed.set(true);
+    }
+
+    /// The reminders of this event that are due at the given time.
+    ///
+    /// A scheduler is expected to poll this, and to snooze or dismiss the returned reminders.
+    /// Reminders of events that already ended are not returned.
+    pub fn pending_reminders(&self, now: &Zoned) -> Vec<Zoned> {
+        if self.imp().reminder_dismissed.get() {
+            return Vec::new();
+        }
+
+        let end = self.timeframe().unwrap_or_default().end();
+        let next_reminder = self.next_reminder();
+        if next_reminder.0 <= now.0 && now.0 < end.0 {
+            vec![next_reminder]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// The time the reminder of this event fires at, taking snoozes into account.
+    fn next_reminder(&self) -> Zoned {
+        self.imp()
+            .snoozed_reminder
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| self.timeframe().unwrap_or_default().start())
+    }
+
+    /// The duration of this event, derived from its time frame.
+    ///
+    /// Bindings that display this value should listen to `notify::timeframe` to stay updated.
+    pub fn duration(&self) -> jiff::Span {
+        self.timeframe().unwrap_or_default().duration()
+    }
+
+    /// The start of the occurrences excluded from the recurrence of this event, sorted.
+    pub fn exdates(&self) -> Vec<Zoned> {
+        self.imp().exdates.borrow().clone()
+    }
+
+    /// Whether the occurrence starting at the given instant is excluded from the recurrence of
+    /// this event.
+    ///
+    /// For all-day events, the occurrence is identified by its date only.
+    pub fn is_excluded(&self, occurrence_start: &Zoned) -> bool {
+        self.imp()
+            .exdates
+            .borrow()
+            .iter()
+            .any(|exdate| self.is_same_occurrence(exdate, occurrence_start))
+    }
+
+    /// Whether both starts identify the same occurrence of this event.
+    ///
+    /// For all-day events, occurrences are identified by their date only.
+    fn is_same_occurrence(&self, start: &Zoned, other_start: &Zoned) -> bool {
+        if self
+            .timeframe()
+            .is_some_and(|timeframe| timeframe.all_day())
+        {
+            start.date() == other_start.date()
+        } else {
+            start.0.timestamp() == other_start.0.timestamp()
+        }
+    }
+
+    /// The rule this event recurs with, if it recurs.
+    pub fn recurrence(&self) -> Option<RecurrenceRule> {
+        self.imp().recurrence.borrow().clone()
+    }
+
+    /// The URI of the recurring event this event overrides an occurrence of, if it is a
+    /// modified occurrence.
+    pub fn recurrence_of(&self) -> Option<String> {
+        self.imp().recurrence_of.borrow().clone()
+    }
+
+    /// The start of the occurrence this event overrides, as generated by the recurrence of the
+    /// event it overrides.
+    pub fn recurrence_id(&self) -> Option<Zoned> {
+        self.imp().recurrence_id.borrow().clone()
+    }
+
+    /// The occurrences of this event sharing some time with the range from `start` to `end`,
+    /// sorted by start.
+    ///
+    /// Recurring events are expanded with their recurrence rule. Occurrences excluded by an
+    /// exception date or rejected by `filter` are skipped, and the ones modified individually are
+    /// replaced by the events overriding them, looked up in the calendar of this event. Events
+    /// that do not recur have their own time frame as single occurrence, and events overriding
+    /// an occurrence have none, as they are returned by the event they override.
+    pub fn occurrences_in_range(
+        &self,
+        start: &Zoned,
+        end: &Zoned,
+        filter: &RecurrenceFilter,
+    ) -> Vec<Occurrence> {
+        let range = Timeframe::new(false, start.clone(), end.clone());
+        let Some(timeframe) = self.timeframe() else {
+            return Vec::new();
+        };
+        if self.imp().recurrence_of.borrow().is_some() {
+            return Vec::new();
+        }
+        let Some(recurrence) = self.recurrence() else {
+            return if timeframe.overlaps(&range) {
+                vec![Occurrence {
+                    event: self.clone(),
+                    timeframe,
+                }]
+            } else {
+                Vec::new()
+            };
+        };
+
+        let uri = self.uri();
+        let overrides = self
+            .calendar()
+            .events()
+            .iter::<Event>()
+            .map(|event| event.expect("Model should not be mutated during iteration"))
+            .filter(|event| event.imp().recurrence_of.borrow().as_ref() == Some(&uri))
+            .filter_map(|event| Some((event.recurrence_id()?, event)))
+            .collect::<Vec<_>>();
+
+        let duration = timeframe.duration();
+        let mut occurrences = Vec::new();
+        for occurrence_start in recurrence.starts(&timeframe.start()) {
+            if occurrence_start.0 >= end.0 {
+                break;
+            }
+            if !self.is_occurrence_kept(&occurrence_start, filter)
+                || overrides.iter().any(|(recurrence_id, _)| {
+                    self.is_same_occurrence(recurrence_id, &occurrence_start)
+                })
+            {
+                continue;
+            }
+
+            let Ok(occurrence_end) = occurrence_start.0.checked_add(duration) else {
+                break;
+            };
+            let occurrence_timeframe =
+                Timeframe::new(timeframe.all_day(), occurrence_start, Zoned(occurrence_end));
+            if occurrence_timeframe.overlaps(&range) {
+                occurrences.push(Occurrence {
+                    event: self.clone(),
+                    timeframe: occurrence_timeframe,
+                });
+            }
+        }
+
+        for (recurrence_id, event) in overrides {
+            let Some(override_timeframe) = event.timeframe() else {
+                continue;
+            };
+            if self.is_occurrence_kept(&recurrence_id, filter)
+                && override_timeframe.overlaps(&range)
+            {
+                occurrences.push(Occurrence {
+                    event,
+                    timeframe: override_timeframe,
+                });
+            }
+        }
+
+        occurrences.sort_by_key(|occurrence| occurrence.timeframe.start().0.timestamp());
+        occurrences
+    }
+
+    /// The people invited to this event.
+    pub fn attendees(&self) -> Vec<Attendee> {
+        self.imp().attendees.borrow().clone()
+    }
+
+    /// The response of the attendee with the given email address, if they are invited.
+    ///
+    /// Email addresses are compared ignoring case.
+    pub fn participation_of(&self, email: &str) -> Option<ParticipationStatus> {
+        self.imp()
+            .attendees
+            .borrow()
+            .iter()
+            .find(|attendee| attendee.email.eq_ignore_ascii_case(email))
+            .map(|attendee| attendee.status)
+    }
+
+    /// Whether the occurrence starting at the given instant is kept, once both the exception
+    /// dates of this event and the given filter are taken into account.
+    pub fn is_occurrence_kept(&self, occurrence_start: &Zoned, filter: &RecurrenceFilter) -> bool {
+        !self.is_excluded(occurrence_start) && filter.accepts(occurrence_start.date())
+    }
+
+    /// Apply the values retrieved from the backend, notifying the properties that changed.
+    pub(crate) fn emit_updated(&self, pre_event: &PreEvent) {
+        let name = pre_event.name.as_str();
+        let description = pre_event.description.as_str();
+        let timeframe = &pre_event.timeframe();
+        let color = pre_event.color;
+        let transparency = pre_event.transparency;
+
+        let uri = self.uri();
+        if name != self.name() {
+            self.set_property("name", name);
+            info!("Event {uri} updated to name {name}");
+        }
+        if description != self.description() {
+            self.set_property("description", description);
+            info!("Event {uri} updated to description {description}");
+        }
+        let old_timeframe = self.timeframe().unwrap_or_default();
+        if timeframe.all_day() != old_timeframe.all_day()
+            || timeframe.start() != old_timeframe.start()
+            || timeframe.end() != old_timeframe.end()
+        {
+            self.set_property("timeframe", timeframe);
+            info!("Event {uri} updated to a new time frame");
+        }
+        if color != self.color() {
+            self.imp().color.replace(color);
+            info!("Event {uri} updated to color {color:?}");
+            self.notify_color();
+        }
+        if transparency != self.transparency() {
+            self.imp().transparency.set(transparency);
+            info!("Event {uri} updated to transparency {transparency:?}");
+            self.notify_transparency();
+        }
+        if pre_event.exdates != *self.imp().exdates.borrow() {
+            self.imp().exdates.replace(pre_event.exdates.clone());
+            info!(
+                "Event {uri} updated to {} exception dates",
+                pre_event.exdates.len()
+            );
+        }
+        if pre_event.attendees != *self.imp().attendees.borrow() {
+            self.imp().attendees.replace(pre_event.attendees.clone());
+            info!(
+                "Event {uri} updated to {} attendees",
+                pre_event.attendees.len()
+            );
+        }
+        if pre_event.recurrence != *self.imp().recurrence.borrow() {
+            self.imp().recurrence.replace(pre_event.recurrence.clone());
+            info!(
+                "Event {uri} updated to recurrence {:?}",
+                pre_event.recurrence
+            );
+        }
+        if pre_event.recurrence_of != *self.imp().recurrence_of.borrow()
+            || pre_event.recurrence_id != *self.imp().recurrence_id.borrow()
+        {
+            self.imp()
+                .recurrence_of
+                .replace(pre_event.recurrence_of.clone());
+            self.imp()
+                .recurrence_id
+                .replace(pre_event.recurrence_id.clone());
+            info!(
+                "Event {uri} updated to override occurrence {:?} of {:?}",
+                pre_event.recurrence_id, pre_event.recurrence_of
+            );
+        }
+    }
+
     /// Signal that this event was deleted.
     pub(super) fn emit_deleted(&self) {
+        if let Some(handler) = self.imp().calendar_color_handler.take() {
+            self.calendar().disconnect(handler);
+        }
         self.emit_by_name::<()>("deleted", &[]);
     }
 
@@ -89,3 +652,205 @@ impl Event {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use gdk::RGBA;
+
+    use super::*;
+    use crate::{Collection, Provider};
+
+    fn calendar() -> Calendar {
+        let provider = Provider::new_detached("urn:provider", "Provider");
+        let collection = Collection::new_detached(&provider, "urn:collection", "Collection");
+        Calendar::new_detached(&collection, "urn:calendar", "Calendar", RGBA::BLUE)
+    }
+
+    fn zoned(s: &str) -> Zoned {
+        s.parse().unwrap()
+    }
+
+    fn timeframe(start: &str, end: &str) -> Timeframe {
+        Timeframe::new(false, zoned(start), zoned(end))
+    }
+
+    fn starts(occurrences: &[Occurrence]) -> Vec<String> {
+        occurrences
+            .iter()
+            .map(|occurrence| occurrence.timeframe.start().to_string())
+            .collect()
+    }
+
+    /// A daily event at 9:00 for an hour, starting on Monday the 3rd of June 2024.
+    fn daily_event(calendar: &Calendar) -> Event {
+        let event = Event::new_detached(
+            calendar,
+            "urn:daily",
+            "Daily",
+            "",
+            &timeframe("2024-06-03T09:00:00[UTC]", "2024-06-03T10:00:00[UTC]"),
+        );
+        event
+            .imp()
+            .recurrence
+            .replace(Some("FREQ=DAILY;COUNT=5".parse().unwrap()));
+        calendar.add_event(&event);
+        event
+    }
+
+    #[test]
+    fn occurrences_skip_exception_dates() {
+        let calendar = calendar();
+        let event = daily_event(&calendar);
+        event
+            .imp()
+            .exdates
+            .replace(vec![zoned("2024-06-04T09:00:00[UTC]")]);
+
+        let occurrences = event.occurrences_in_range(
+            &zoned("2024-06-04T00:00:00[UTC]"),
+            &zoned("2024-06-06T00:00:00[UTC]"),
+            &RecurrenceFilter::default(),
+        );
+        assert_eq!(starts(&occurrences), ["2024-06-05T09:00:00+00:00[UTC]"]);
+        assert_eq!(
+            occurrences[0].timeframe.end(),
+            zoned("2024-06-05T10:00:00[UTC]")
+        );
+    }
+
+    #[test]
+    fn overridden_occurrences_are_replaced() {
+        let calendar = calendar();
+        let event = daily_event(&calendar);
+        let moved = Event::new_detached(
+            &calendar,
+            "urn:moved",
+            "Daily, later",
+            "",
+            &timeframe("2024-06-04T14:00:00[UTC]", "2024-06-04T15:00:00[UTC]"),
+        );
+        moved.imp().recurrence_of.replace(Some(event.uri()));
+        moved
+            .imp()
+            .recurrence_id
+            .replace(Some(zoned("2024-06-04T09:00:00[UTC]")));
+        calendar.add_event(&moved);
+
+        let occurrences = event.occurrences_in_range(
+            &zoned("2024-06-04T00:00:00[UTC]"),
+            &zoned("2024-06-05T00:00:00[UTC]"),
+            &RecurrenceFilter::default(),
+        );
+        assert_eq!(starts(&occurrences), ["2024-06-04T14:00:00+00:00[UTC]"]);
+        assert_eq!(occurrences[0].event, moved);
+
+        // The override is only returned through the event it overrides
+        assert!(
+            moved
+                .occurrences_in_range(
+                    &zoned("2024-06-04T00:00:00[UTC]"),
+                    &zoned("2024-06-05T00:00:00[UTC]"),
+                    &RecurrenceFilter::default(),
+                )
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn occurrences_stop_after_the_count() {
+        let calendar = calendar();
+        let event = daily_event(&calendar);
+
+        let occurrences = event.occurrences_in_range(
+            &zoned("2024-06-01T00:00:00[UTC]"),
+            &zoned("2024-06-30T00:00:00[UTC]"),
+            &RecurrenceFilter::default(),
+        );
+        assert_eq!(occurrences.len(), 5);
+    }
+
+    #[test]
+    fn working_days_filter_skips_weekend_occurrences_within_the_count() {
+        let calendar = calendar();
+        let event = daily_event(&calendar);
+        event
+            .imp()
+            .recurrence
+            .replace(Some("FREQ=DAILY;COUNT=7".parse().unwrap()));
+
+        // Saturday the 8th and Sunday the 9th are skipped, but still count
+        let occurrences = event.occurrences_in_range(
+            &zoned("2024-06-01T00:00:00[UTC]"),
+            &zoned("2024-06-30T00:00:00[UTC]"),
+            &RecurrenceFilter::working_days(),
+        );
+        assert_eq!(
+            starts(&occurrences),
+            [
+                "2024-06-03T09:00:00+00:00[UTC]",
+                "2024-06-04T09:00:00+00:00[UTC]",
+                "2024-06-05T09:00:00+00:00[UTC]",
+                "2024-06-06T09:00:00+00:00[UTC]",
+                "2024-06-07T09:00:00+00:00[UTC]",
+            ]
+        );
+    }
+
+    #[test]
+    fn working_days_filter_applies_until_the_end_of_the_recurrence() {
+        let calendar = calendar();
+        let event = daily_event(&calendar);
+        event
+            .imp()
+            .recurrence
+            .replace(Some("FREQ=DAILY;UNTIL=20240611".parse().unwrap()));
+
+        let occurrences = event.occurrences_in_range(
+            &zoned("2024-06-06T00:00:00[UTC]"),
+            &zoned("2024-06-30T00:00:00[UTC]"),
+            &RecurrenceFilter::working_days(),
+        );
+        assert_eq!(
+            starts(&occurrences),
+            [
+                "2024-06-06T09:00:00+00:00[UTC]",
+                "2024-06-07T09:00:00+00:00[UTC]",
+                "2024-06-10T09:00:00+00:00[UTC]",
+                "2024-06-11T09:00:00+00:00[UTC]",
+            ]
+        );
+    }
+
+    #[test]
+    fn events_without_recurrence_have_a_single_occurrence() {
+        let calendar = calendar();
+        let event = Event::new_detached(
+            &calendar,
+            "urn:single",
+            "Single",
+            "",
+            &timeframe("2024-06-03T09:00:00[UTC]", "2024-06-03T10:00:00[UTC]"),
+        );
+
+        let range = (
+            zoned("2024-06-03T00:00:00[UTC]"),
+            zoned("2024-06-04T00:00:00[UTC]"),
+        );
+        assert_eq!(
+            event
+                .occurrences_in_range(&range.0, &range.1, &RecurrenceFilter::working_days())
+                .len(),
+            1
+        );
+        assert!(
+            event
+                .occurrences_in_range(
+                    &range.1,
+                    &zoned("2024-06-05T00:00:00[UTC]"),
+                    &RecurrenceFilter::default()
+                )
+                .is_empty()
+        );
+    }
+}
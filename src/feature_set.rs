@@ -0,0 +1,135 @@
+use gdk::{gio, glib};
+use tracing::{info, warn};
+use tsparql::{SparqlConnection, SparqlCursor};
+
+/// The query checking which optional predicates the ontology has.
+const PROBE_QUERY: &str = "SELECT
+    (EXISTS { ccm:trashed a rdf:Property } AS ?trash)
+    (EXISTS { ccm:eventLocation a rdf:Property } AS ?location)
+    (EXISTS { ccm:eventRecurrence a rdf:Property } AS ?recurrence)
+    (EXISTS { ccm:eventStatus a rdf:Property } AS ?status)
+    (EXISTS { ccm:category a rdf:Property } AS ?category)
+    (EXISTS { ccm:uid a rdf:Property } AS ?uid)
+    (EXISTS { ccm:transparency a rdf:Property } AS ?transparency)
+    (EXISTS { ccm:exdate a rdf:Property } AS ?exdate)
+    (EXISTS { ccm:subscriptionUrl a rdf:Property } AS ?subscription)
+    (EXISTS { ccm:syncToken a rdf:Property } AS ?sync_token)
+    (EXISTS { ccm:attendee a rdf:Property } AS ?attendee)
+    (EXISTS { ccm:sortOrder a rdf:Property } AS ?sort_order)
+    (EXISTS { ccm:accountIdentity a rdf:Property } AS ?account_identity)
+    (EXISTS { ccm:source a rdf:Property } AS ?source)
+    (EXISTS { ccm:calendarDescription a rdf:Property } AS ?calendar_description)
+    (EXISTS { ccm:recurrenceId a rdf:Property } AS ?overrides)
+WHERE {}";
+
+/// The optional parts of the ontology that the database supports.
+///
+/// Older deployments of the ontology may lack some predicates. Queries only reference the
+/// predicates listed here when the matching feature is supported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Boxed)]
+#[boxed_type(name = "FeatureSet")]
+pub struct FeatureSet {
+    /// Calendars can be moved to the trash (`ccm:trashed`).
+    pub trash: bool,
+    /// Events have a location (`ccm:eventLocation`).
+    pub location: bool,
+    /// Events have a recurrence rule (`ccm:eventRecurrence`).
+    pub recurrence: bool,
+    /// Events have a status (`ccm:eventStatus`).
+    pub status: bool,
+    /// Events have categories (`ccm:category`).
+    pub category: bool,
+    /// Events have a stable identifier shared with other calendar systems (`ccm:uid`).
+    pub uid: bool,
+    /// Events can be marked as not blocking time (`ccm:transparency`).
+    pub transparency: bool,
+    /// Recurring events have exception dates (`ccm:exdate`).
+    pub exdate: bool,
+    /// Calendars can be subscriptions to remote iCalendar feeds (`ccm:subscriptionUrl`).
+    pub subscription: bool,
+    /// Calendars carry a token identifying their state on the remote server (`ccm:syncToken`).
+    pub sync_token: bool,
+    /// Events have attendees with a participation status (`ccm:attendee`).
+    pub attendee: bool,
+    /// Calendars have a position set by the user (`ccm:sortOrder`).
+    pub sort_order: bool,
+    /// Providers have the identity of their account (`ccm:accountIdentity`).
+    pub account_identity: bool,
+    /// Calendars have the URL they are synced from (`ccm:source`).
+    pub source: bool,
+    /// Calendars have a description (`ccm:calendarDescription`).
+    pub calendar_description: bool,
+    /// Events can override an occurrence of a recurring event (`ccm:recurrenceOf` and
+    /// `ccm:recurrenceId`).
+    pub overrides: bool,
+}
+
+impl FeatureSet {
+    /// Probe the ontology of the database for the optional predicates.
+    ///
+    /// If the probe fails, no optional feature is considered supported.
+    pub(crate) fn probe(read_connection: &SparqlConnection) -> Self {
+        let cursor = match read_connection.query(PROBE_QUERY, None::<&gio::Cancellable>) {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                warn!("Failed to probe the ontology: {err}");
+                return Self::default();
+            }
+        };
+
+        let next = cursor.next(None::<&gio::Cancellable>);
+        Self::from_probe(&cursor, next)
+    }
+
+    /// Probe the ontology of the database for the optional predicates, without blocking.
+    ///
+    /// See [`FeatureSet::probe`].
+    pub(crate) async fn probe_future(read_connection: &SparqlConnection) -> Self {
+        let cursor = match read_connection.query_future(PROBE_QUERY).await {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                warn!("Failed to probe the ontology: {err}");
+                return Self::default();
+            }
+        };
+
+        let next = cursor.next_future().await;
+        Self::from_probe(&cursor, next)
+    }
+
+    /// Read the result of the probe, given the cursor and the result of moving it to its row.
+    fn from_probe(cursor: &SparqlCursor, next: Result<bool, glib::Error>) -> Self {
+        match next {
+            Ok(true) => {
+                let features = Self {
+                    trash: cursor.is_boolean(0),
+                    location: cursor.is_boolean(1),
+                    recurrence: cursor.is_boolean(2),
+                    status: cursor.is_boolean(3),
+                    category: cursor.is_boolean(4),
+                    uid: cursor.is_boolean(5),
+                    transparency: cursor.is_boolean(6),
+                    exdate: cursor.is_boolean(7),
+                    subscription: cursor.is_boolean(8),
+                    sync_token: cursor.is_boolean(9),
+                    attendee: cursor.is_boolean(10),
+                    sort_order: cursor.is_boolean(11),
+                    account_identity: cursor.is_boolean(12),
+                    source: cursor.is_boolean(13),
+                    calendar_description: cursor.is_boolean(14),
+                    overrides: cursor.is_boolean(15),
+                };
+                info!("Supported features: {features:?}");
+                features
+            }
+            Ok(false) => {
+                warn!("Probing the ontology returned no result");
+                Self::default()
+            }
+            Err(err) => {
+                warn!("Failed to probe the ontology: {err}");
+                Self::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use gdk::{RGBA, gio, glib, prelude::*};
+
+use crate::{Calendar, Event};
+
+/// The maximum length of a content line, in octets, before it is folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Write a calendar and its events as a `VCALENDAR` component.
+///
+/// The name and color of the calendar are written as `X-WR-CALNAME` and
+/// `X-APPLE-CALENDAR-COLOR`, which most clients understand.
+pub(crate) fn write_calendar(
+    stream: &impl IsA<gio::OutputStream>,
+    calendar: &Calendar,
+) -> Result<(), glib::Error> {
+    write_line(stream, "BEGIN:VCALENDAR")?;
+    write_line(stream, "VERSION:2.0")?;
+    write_line(stream, "PRODID:-//ccm//ccm//EN")?;
+    write_line(
+        stream,
+        &format!("X-WR-CALNAME:{}", escape_text(&calendar.name())),
+    )?;
+    if let Some(color) = calendar.color() {
+        write_line(
+            stream,
+            &format!("X-APPLE-CALENDAR-COLOR:{}", hex_color(color)),
+        )?;
+    }
+
+    let dtstamp = format_utc(&jiff::Zoned::now());
+    for event in calendar.events().iter::<Event>() {
+        let event = event.expect("Model should not be mutated during iteration");
+        write_event(stream, &event, &dtstamp)?;
+    }
+
+    write_line(stream, "END:VCALENDAR")
+}
+
+/// Write an event as a `VEVENT` component.
+fn write_event(
+    stream: &impl IsA<gio::OutputStream>,
+    event: &Event,
+    dtstamp: &str,
+) -> Result<(), glib::Error> {
+    let timeframe = event.timeframe().unwrap_or_default();
+
+    write_line(stream, "BEGIN:VEVENT")?;
+    write_line(stream, &format!("UID:{}", escape_text(&event.uri())))?;
+    write_line(stream, &format!("DTSTAMP:{dtstamp}"))?;
+    if timeframe.all_day() {
+        write_line(
+            stream,
+            &format!(
+                "DTSTART;VALUE=DATE:{}",
+                timeframe.start().date().strftime("%Y%m%d")
+            ),
+        )?;
+        write_line(
+            stream,
+            &format!(
+                "DTEND;VALUE=DATE:{}",
+                timeframe.end().date().strftime("%Y%m%d")
+            ),
+        )?;
+    } else {
+        write_line(
+            stream,
+            &format!("DTSTART:{}", format_utc(&timeframe.start().0)),
+        )?;
+        write_line(stream, &format!("DTEND:{}", format_utc(&timeframe.end().0)))?;
+    }
+    write_line(stream, &format!("SUMMARY:{}", escape_text(&event.name())))?;
+    if !event.description().is_empty() {
+        write_line(
+            stream,
+            &format!("DESCRIPTION:{}", escape_text(&event.description())),
+        )?;
+    }
+    write_line(stream, "END:VEVENT")
+}
+
+/// Write a content line, folding it if it is too long.
+fn write_line(stream: &impl IsA<gio::OutputStream>, line: &str) -> Result<(), glib::Error> {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+
+    stream
+        .write_all(folded.as_bytes(), None::<&gio::Cancellable>)
+        .map(|_| ())
+}
+
+/// Escape a text value.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Format an instant as a UTC date-time.
+fn format_utc(zoned: &jiff::Zoned) -> String {
+    zoned
+        .with_time_zone(jiff::tz::TimeZone::UTC)
+        .strftime("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Format a color as `#rrggbb`.
+fn hex_color(color: RGBA) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue())
+    )
+}
@@ -1,22 +1,52 @@
 mod calendar;
+mod collation;
 mod collection;
 mod collections_model;
+mod csv;
+mod error;
 mod event;
+mod feature_set;
+mod ical;
 mod manager;
+mod metrics;
+mod new_resource;
 mod pre_resource;
 mod provider;
+mod recurrence;
+mod recurrence_filter;
 mod resource;
+mod resource_data;
+mod resource_watcher;
+mod retry_policy;
+mod search_options;
+mod search_result;
+mod time_zones;
 mod timeframe;
+mod undo;
 mod utils;
 
 pub use calendar::*;
+pub use collation::*;
 pub use collection::*;
 pub use collections_model::*;
+pub use error::*;
 pub use event::*;
+pub use feature_set::*;
 pub use manager::*;
+pub use metrics::*;
+pub use new_resource::*;
 pub use provider::*;
+pub use recurrence::*;
+pub use recurrence_filter::*;
 pub use resource::*;
+pub use resource_data::*;
+pub use resource_watcher::*;
+pub use retry_policy::*;
+pub use search_options::*;
+pub use search_result::*;
+pub use time_zones::*;
 pub use timeframe::*;
+pub use undo::*;
 
 #[doc(no_inline)]
 pub use jiff;
@@ -1,13 +1,15 @@
 use std::{
-    cell::{OnceCell, RefCell},
-    collections::HashMap,
-    sync::{Mutex, MutexGuard},
+    cell::{Cell, OnceCell, RefCell},
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+    sync::{LazyLock, Mutex, MutexGuard},
+    task::{Poll, Waker},
 };
 
 use gdk::{
     RGBA,
     gio::{self, BusType, DBusCallFlags, DBusProxy, DBusProxyFlags, ListStore},
-    glib::{self, Object, clone},
+    glib::{self, Object, clone, closure_local, subclass::Signal, translate::IntoGlib},
     prelude::*,
     subclass::prelude::*,
 };
@@ -15,23 +17,144 @@ use tracing::{debug, info, warn};
 use tsparql::{Notifier, NotifierEvent, NotifierEventType, SparqlConnection, prelude::*};
 
 use crate::{
-    Calendar, Collection, CollectionsModel, Event, Provider, Resource, Timeframe, Zoned,
-    pre_resource::PreResource, spawn,
+    Calendar, Collection, CollectionsModel, DeletedEvent, Event, EventTransparency, FeatureSet,
+    Instant, MalformedTimePolicy, Metrics, NewCalendar, NewEvent, OperationMetrics,
+    ParticipationStatus, PreResourceError, Provider, ProviderKind, RecurrenceFilter, Resource,
+    ResourceChangeKind, ResourceWatcher, RetryPolicy, SearchOptions, SearchResult, Timeframe,
+    UndoAction, ValidationError, WriteError, Zoned, ical, name_collation_key,
+    pre_resource::{PreCalendar, PreEvent, PreProvider, PreResource},
+    utils::SignalWatches,
 };
 
+/// The maximum number of resources re-fetched because of unknown notifier events, per batch.
+const MAX_UNKNOWN_EVENT_REFETCHES: usize = 64;
+
+/// The colors suggested for new calendars, in order of preference.
+const CALENDAR_PALETTE: [&str; 10] = [
+    "#3584e4", "#33d17a", "#f6d32d", "#ff7800", "#e01b24", "#9141ac", "#986a44", "#2190a4",
+    "#e66100", "#c061cb",
+];
+
+/// How long to wait after a change before refreshing live searches, in milliseconds.
+const LIVE_SEARCH_DEBOUNCE_MS: u32 = 300;
+
+/// The maximum number of matched events loaded by a search because they were not in the pool.
+const MAX_SEARCH_FALLBACK_LOADS: usize = 32;
+
+/// How far ahead to look for a free slot, in days.
+const FREE_SLOT_SEARCH_DAYS: i64 = 366;
+
+/// The number of writes that can be undone.
+const MAX_UNDO_ACTIONS: usize = 32;
+
+/// The maximum number of characters of names, unless configured otherwise.
+const DEFAULT_MAX_NAME_LENGTH: u32 = 255;
+
+/// How many times a created resource whose parent is missing is retried, unless configured
+/// otherwise.
+const DEFAULT_MAX_PENDING_RETRIES: u32 = 8;
+
+/// How long a batch can last before it is ended automatically, in seconds.
+const MAX_BATCH_DURATION_SECS: u32 = 60;
+
 mod imp {
     use super::*;
 
     #[derive(Debug, Default, glib::Properties)]
     #[properties(wrapper_type = super::Manager)]
     pub struct Manager {
+        /// The connection to the database, opened synchronously on construction if not set.
+        #[property(set, construct_only)]
         read_connection: OnceCell<SparqlConnection>,
+        /// The proxy of the write service, opened synchronously on construction if not set.
+        #[property(set, construct_only)]
         write_connection: OnceCell<DBusProxy>,
+        /// The notifier of changes to the database, created on construction if not set.
+        #[property(set, construct_only)]
         notifier: OnceCell<Notifier>,
+        /// The session bus, to follow the presence of the services. It is retrieved synchronously
+        /// on construction if not set.
+        #[property(set, construct_only)]
+        session_bus: OnceCell<gio::DBusConnection>,
         resource_pool: OnceCell<Mutex<HashMap<String, Resource>>>,
+        #[property(get, construct_only)]
+        read_only: Cell<bool>,
+        #[property(get, construct_only, nullable)]
+        main_context: RefCell<Option<glib::MainContext>>,
+        /// The URIs of the providers to load resources of, or an empty list to load every provider.
+        #[property(get, construct_only)]
+        allowed_providers: RefCell<glib::StrV>,
+        /// The graph the manager follows changes of, or `None` to follow every graph.
+        #[property(get, construct_only, nullable)]
+        graph: RefCell<Option<String>>,
+        /// Whether calendars are only loaded when their collection is first expanded, see
+        /// [`Collection::ensure_calendars_loaded`].
+        #[property(get, construct_only)]
+        lazy_calendars: Cell<bool>,
         #[property(get)]
         collections_model: OnceCell<CollectionsModel>,
         events_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) cancellable: RefCell<gio::Cancellable>,
+        /// The optional parts of the ontology, probed synchronously on construction if not set.
+        #[property(set, construct_only)]
+        supported_features: OnceCell<FeatureSet>,
+        pub(super) write_retry: Cell<RetryPolicy>,
+        /// The number of events left to start today, in the system time zone.
+        ///
+        /// Events hidden from the event lists, see [`Manager::is_hidden`], are not counted.
+        #[property(get)]
+        events_remaining_today: Cell<u32>,
+        /// The email address of the user of the application, to find their own responses.
+        #[property(get, set, nullable)]
+        user_email: RefCell<Option<String>>,
+        /// The maximum number of characters of the names written through this manager.
+        #[property(get, set, construct, default = DEFAULT_MAX_NAME_LENGTH)]
+        max_name_length: Cell<u32>,
+        /// How many later changes a created resource whose parent is missing waits for its
+        /// parent, before it is dropped.
+        #[property(get, set, construct, default = DEFAULT_MAX_PENDING_RETRIES)]
+        max_pending_retries: Cell<u32>,
+        /// How events ending before they start are fixed when they are loaded.
+        #[property(get, set, builder(MalformedTimePolicy::default()))]
+        malformed_time_policy: Cell<MalformedTimePolicy>,
+        /// Whether the durations of queries and of the handling of changes are recorded, see
+        /// [`Manager::metrics`](super::Manager::metrics).
+        #[property(get, set)]
+        collect_metrics: Cell<bool>,
+        pub(super) metrics: Cell<Metrics>,
+        /// Whether events declined by the user are left out of the event lists.
+        #[property(get, set)]
+        hide_declined: Cell<bool>,
+        /// The thread the manager was created on, the only one it may be used from.
+        owner_thread: OnceCell<std::thread::ThreadId>,
+        pub(super) undo_stack: RefCell<VecDeque<UndoAction>>,
+        pub(super) redo_stack: RefCell<Vec<UndoAction>>,
+        /// The searches to refresh when resources change, with the query, options and results.
+        pub(super) live_searches: RefCell<Vec<(String, SearchOptions, glib::WeakRef<ListStore>)>>,
+        live_search_refresh_pending: Cell<bool>,
+        /// Whether the read service and, unless the manager is read-only, the write service are
+        /// running.
+        #[property(get)]
+        connected: Cell<bool>,
+        read_service_running: Cell<bool>,
+        write_service_running: Cell<bool>,
+        name_owner_subscription: RefCell<Option<(gio::DBusConnection, gio::SignalSubscriptionId)>>,
+        /// The number of batches begun and not ended yet.
+        batch_depth: Cell<u32>,
+        /// The changes received during the current batch, in the order they arrived.
+        batched_changes: RefCell<Vec<(glib::GString, ResourceChangeKind)>>,
+        /// The timeout ending the current batch if it lasts too long.
+        batch_timeout: RefCell<Option<glib::JoinHandle<()>>>,
+        /// The URIs of the created resources waiting for their parent, with the number of times
+        /// they were retried.
+        pending_children: RefCell<HashMap<String, u32>>,
+        /// The error that stopped the last load of resources, if any.
+        pub(super) load_error: RefCell<Option<glib::Error>>,
+        /// The URIs of the resources skipped while loading because their parent does not exist.
+        pub(super) orphaned_resources: RefCell<Vec<String>>,
+        /// The number of retrievals and refreshes spawned and not finished yet.
+        pub(super) pending_work: Cell<u32>,
+        pub(super) watchers: RefCell<HashMap<String, Vec<glib::WeakRef<ResourceWatcher>>>>,
     }
 
     #[glib::object_subclass]
@@ -43,25 +166,58 @@ mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for Manager {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
+                vec![
+                    Signal::builder("structure-ready").build(),
+                    Signal::builder("loading-finished").build(),
+                    Signal::builder("resource-changed")
+                        .param_types([String::static_type(), ResourceChangeKind::static_type()])
+                        .build(),
+                ]
+            });
+            SIGNALS.as_ref()
+        }
+
+        fn dispose(&self) {
+            if let Some((bus, subscription)) = self.name_owner_subscription.take() {
+                bus.signal_unsubscribe(subscription);
+            }
+        }
+
         fn constructed(&self) {
             self.parent_constructed();
 
+            self.owner_thread
+                .get_or_init(|| std::thread::current().id());
+
+            self.main_context
+                .borrow_mut()
+                .get_or_insert_with(glib::MainContext::ref_thread_default);
+
             self.read_connection.get_or_init(|| {
                 SparqlConnection::bus_new("io.gitlab.TitouanReal.CcmRead", None, None).unwrap()
             });
 
-            self.write_connection.get_or_init(|| {
-                DBusProxy::for_bus_sync(
-                    BusType::Session,
-                    DBusProxyFlags::NONE,
-                    None,
-                    "io.gitlab.TitouanReal.CcmWrite",
-                    "/io/gitlab/TitouanReal/CcmWrite/Provider",
-                    "io.gitlab.TitouanReal.CcmWrite.Provider",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-            });
+            if !self.read_only.get() {
+                self.write_connection.get_or_init(|| {
+                    DBusProxy::for_bus_sync(
+                        BusType::Session,
+                        DBusProxyFlags::NONE,
+                        None,
+                        "io.gitlab.TitouanReal.CcmWrite",
+                        "/io/gitlab/TitouanReal/CcmWrite/Provider",
+                        "io.gitlab.TitouanReal.CcmWrite.Provider",
+                        None::<&gio::Cancellable>,
+                    )
+                    .unwrap()
+                });
+            }
+
+            self.supported_features
+                .get_or_init(|| FeatureSet::probe(self.read_connection()));
+
+            self.watch_services();
 
             self.notifier
                 .get_or_init(|| SparqlConnection::create_notifier(self.read_connection()).unwrap());
@@ -72,39 +228,322 @@ mod imp {
             self.collections_model
                 .get_or_init(CollectionsModel::default);
 
-            spawn!(clone!(
+            self.spawn_work(clone!(
                 #[weak(rename_to = imp)]
                 self,
                 async move {
-                    imp.retrieve_resources();
+                    imp.load_resources();
                 }
             ));
 
+            // Events start as time passes, so the count of remaining events needs to be refreshed
+            // even when nothing changes in the database
+            let obj = self.obj().downgrade();
+            self.spawn(async move {
+                loop {
+                    glib::timeout_future_seconds(60).await;
+                    let Some(obj) = obj.upgrade() else {
+                        break;
+                    };
+                    obj.imp().update_events_remaining_today();
+                }
+            });
+
+            self.connect_notifier();
+
+            // Live searches filter out declined events, so they depend on these properties
+            let obj = self.obj();
+            obj.connect_hide_declined_notify(|obj| {
+                obj.imp().schedule_live_search_refresh();
+                obj.imp().update_events_remaining_today();
+            });
+            obj.connect_user_email_notify(|obj| {
+                obj.imp().schedule_live_search_refresh();
+                obj.imp().update_events_remaining_today();
+            });
+        }
+    }
+
+    impl Manager {
+        /// Follow the presence of the services on the bus, to keep the `connected` property up
+        /// to date.
+        fn watch_services(&self) {
+            // The connections were just opened, so the services are assumed to be running until
+            // the bus reports otherwise
+            self.read_service_running.set(true);
+            self.write_service_running.set(
+                self.write_connection
+                    .get()
+                    .is_some_and(|proxy| proxy.name_owner().is_some()),
+            );
+            self.update_connected();
+
+            let bus = match self.session_bus.get() {
+                Some(bus) => bus.clone(),
+                None => match gio::bus_get_sync(BusType::Session, None::<&gio::Cancellable>) {
+                    Ok(bus) => bus,
+                    Err(err) => {
+                        warn!("Failed to watch the services: {err}");
+                        return;
+                    }
+                },
+            };
+            let subscription = bus.signal_subscribe(
+                Some("org.freedesktop.DBus"),
+                Some("org.freedesktop.DBus"),
+                Some("NameOwnerChanged"),
+                Some("/org/freedesktop/DBus"),
+                None,
+                gio::DBusSignalFlags::NONE,
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_, _, _, _, _, parameters| {
+                        let Some((name, _old_owner, new_owner)) =
+                            parameters.get::<(String, String, String)>()
+                        else {
+                            return;
+                        };
+                        let running = !new_owner.is_empty();
+                        match name.as_str() {
+                            "io.gitlab.TitouanReal.CcmRead" => {
+                                imp.read_service_running.set(running);
+                            }
+                            "io.gitlab.TitouanReal.CcmWrite" => {
+                                imp.write_service_running.set(running);
+                            }
+                            _ => return,
+                        }
+                        if !running {
+                            warn!("Service {name} vanished from the bus");
+                        }
+                        imp.update_connected();
+                    }
+                ),
+            );
+            self.name_owner_subscription
+                .replace(Some((bus, subscription)));
+        }
+
+        fn update_connected(&self) {
+            let connected = self.read_service_running.get()
+                && (self.read_only.get() || self.write_service_running.get());
+            if self.connected.replace(connected) != connected {
+                self.obj().notify_connected();
+            }
+        }
+
+        /// Start handling the events of the notifier.
+        fn connect_notifier(&self) {
             self.events_handler
                 .replace(Some(self.notifier().connect_events(clone!(
                     #[weak(rename_to = imp)]
                     self,
                     move |_notifier: &tsparql::Notifier,
                           _service: Option<&str>,
-                          _graph: Option<&str>,
+                          graph: Option<&str>,
                           events: Vec<NotifierEvent>| {
-                        imp.handle_notifier_events(events);
+                        if let Some(followed_graph) = imp.graph.borrow().as_deref()
+                            && graph.is_some_and(|graph| graph != followed_graph)
+                        {
+                            debug!("Ignoring {} events from graph {graph:?}", events.len());
+                            return;
+                        }
+                        imp.handle_notifier_events(graph, events);
                     },
                 ))));
         }
-    }
 
-    impl Manager {
+        /// Stop handling the events of the notifier.
+        fn disconnect_notifier(&self) {
+            if let Some(handler) = self.events_handler.take() {
+                self.notifier().disconnect(handler);
+            }
+        }
+
+        /// Drop every resource, then load them again from the database.
+        pub(super) fn reset(&self) {
+            self.assert_owner_thread();
+            self.disconnect_notifier();
+            self.obj().cancel_pending_queries();
+
+            let collections = self
+                .resource_pool()
+                .values()
+                .filter_map(|resource| match resource {
+                    Resource::Collection(collection) => Some(collection.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            // Deleting the collections empties the stores of their providers and of the
+            // collections model, and deletes their calendars and events
+            for collection in collections {
+                collection.emit_deleted();
+            }
+            self.resource_pool().clear();
+            self.orphaned_resources.borrow_mut().clear();
+            self.pending_children.borrow_mut().clear();
+
+            self.undo_stack.borrow_mut().clear();
+            self.redo_stack.borrow_mut().clear();
+
+            self.connect_notifier();
+            self.spawn_work(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    imp.load_resources();
+                }
+            ));
+        }
         pub(super) fn read_connection(&self) -> &SparqlConnection {
             self.read_connection
                 .get()
                 .expect("read connection should be initialized")
         }
 
-        pub(super) fn write_connection(&self) -> &DBusProxy {
-            self.write_connection
+        /// The write connection, or [`WriteError::ReadOnly`] if the manager is read-only.
+        pub(super) fn write_connection(&self) -> Result<&DBusProxy, WriteError> {
+            if self.read_only.get() {
+                return Err(WriteError::ReadOnly);
+            }
+
+            Ok(self
+                .write_connection
+                .get()
+                .expect("write connection should be initialized"))
+        }
+
+        /// Spawn a local future on the main context of this manager.
+        fn spawn<F: Future<Output = ()> + 'static>(&self, future: F) -> glib::JoinHandle<()> {
+            self.main_context
+                .borrow()
+                .as_ref()
+                .expect("main context should be initialized")
+                .spawn_local(future)
+        }
+
+        /// Spawn a local future on the main context of this manager, counting it as pending work
+        /// until it completes.
+        fn spawn_work<F: Future<Output = ()> + 'static>(&self, future: F) -> glib::JoinHandle<()> {
+            self.pending_work.set(self.pending_work.get() + 1);
+
+            let obj = self.obj().downgrade();
+            self.spawn(async move {
+                future.await;
+                if let Some(obj) = obj.upgrade() {
+                    let pending_work = &obj.imp().pending_work;
+                    pending_work.set(pending_work.get() - 1);
+                }
+            })
+        }
+
+        /// Check, in debug builds, that the manager is used from the thread it was created on.
+        ///
+        /// The resources held by the manager are updated from callbacks of its main context, so
+        /// using it from another thread would corrupt them.
+        pub(super) fn assert_owner_thread(&self) {
+            debug_assert_eq!(
+                self.owner_thread.get(),
+                Some(&std::thread::current().id()),
+                "Manager should only be used from the thread it was created on"
+            );
+        }
+
+        /// Call a method of the write service.
+        ///
+        /// Failures are not retried, as waiting between retries would block the calling thread.
+        /// See [`Self::call_write_future`].
+        pub(super) fn call_write(
+            &self,
+            method: &str,
+            parameters: &glib::Variant,
+        ) -> Result<glib::Variant, WriteError> {
+            self.assert_owner_thread();
+
+            Ok(self.write_connection()?.call_sync(
+                method,
+                Some(parameters),
+                DBusCallFlags::NONE,
+                -1,
+                None::<&gio::Cancellable>,
+            )?)
+        }
+
+        /// Call a method of the write service asynchronously, retrying transient failures
+        /// according to the write retry policy.
+        pub(super) async fn call_write_future(
+            &self,
+            method: &str,
+            parameters: &glib::Variant,
+        ) -> Result<glib::Variant, WriteError> {
+            self.assert_owner_thread();
+            let write_connection = self.write_connection()?;
+            let retry_policy = self.write_retry.get();
+
+            let mut retry = 0;
+            loop {
+                match write_connection
+                    .call_future(method, Some(parameters), DBusCallFlags::NONE, -1)
+                    .await
+                {
+                    Ok(result) => return Ok(result),
+                    Err(err)
+                        if retry < retry_policy.max_retries && RetryPolicy::is_transient(&err) =>
+                    {
+                        let delay = retry_policy.delay(retry);
+                        warn!("Write {method} failed, retrying in {delay:?}: {err}");
+                        glib::timeout_future(delay).await;
+                        retry += 1;
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+
+        /// Call a method of the write service that creates a resource, returning its URI.
+        pub(super) fn call_create(
+            &self,
+            method: &str,
+            parameters: &glib::Variant,
+        ) -> Result<String, WriteError> {
+            let result = self.call_write(method, parameters)?;
+            Self::created_uri(method, &result)
+        }
+
+        /// Call a method of the write service that creates a resource asynchronously, retrying
+        /// transient failures, and return its URI.
+        pub(super) async fn call_create_future(
+            &self,
+            method: &str,
+            parameters: &glib::Variant,
+        ) -> Result<String, WriteError> {
+            let result = self.call_write_future(method, parameters).await?;
+            Self::created_uri(method, &result)
+        }
+
+        /// The URI returned by a method of the write service that creates a resource.
+        fn created_uri(method: &str, result: &glib::Variant) -> Result<String, WriteError> {
+            let Some((uri,)) = result.get::<(String,)>() else {
+                return Err(glib::Error::new(
+                    gio::IOErrorEnum::InvalidData,
+                    &format!("{method} returned {} instead of a URI", result.type_()),
+                )
+                .into());
+            };
+
+            Ok(uri)
+        }
+
+        /// The cancellable shared by the queries currently in flight.
+        pub(super) fn cancellable(&self) -> gio::Cancellable {
+            self.cancellable.borrow().clone()
+        }
+
+        pub(super) fn supported_features(&self) -> &FeatureSet {
+            self.supported_features
                 .get()
-                .expect("write connection should be initialized")
+                .expect("supported features should be initialized")
         }
 
         fn notifier(&self) -> &Notifier {
@@ -120,53 +559,255 @@ mod imp {
                 .unwrap()
         }
 
-        fn retrieve_resources(&self) {
-            self.retrieve_providers();
-            self.retrieve_collections();
-            self.retrieve_calendars();
-            self.retrieve_events();
+        /// Retrieve every resource, then signal that loading finished, even if it failed.
+        ///
+        /// The error of a failed load is kept in `load_error`.
+        fn load_resources(&self) {
+            let result = self.retrieve_resources();
+            if let Err(err) = &result {
+                warn!("Failed to load resources: {err}");
+            }
+            self.load_error.replace(result.err());
+
+            self.report_orphaned_resources();
+            self.obj().emit_by_name::<()>("loading-finished", &[]);
+            self.update_events_remaining_today();
+        }
+
+        fn retrieve_resources(&self) -> Result<(), glib::Error> {
+            self.timed(
+                |metrics| &mut metrics.provider_retrievals,
+                || self.retrieve_providers(),
+            )?;
+            self.timed(
+                |metrics| &mut metrics.collection_retrievals,
+                || self.retrieve_collections(),
+            )?;
+            if self.lazy_calendars.get() {
+                self.obj().emit_by_name::<()>("structure-ready", &[]);
+            } else {
+                self.timed(
+                    |metrics| &mut metrics.calendar_retrievals,
+                    || self.retrieve_calendars(None),
+                )?;
+                self.obj().emit_by_name::<()>("structure-ready", &[]);
+                self.timed(
+                    |metrics| &mut metrics.event_retrievals,
+                    || self.retrieve_events(None),
+                )?;
+                let collections = self
+                    .resource_pool()
+                    .values()
+                    .filter_map(|resource| match resource {
+                        Resource::Collection(collection) => Some(collection.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                for collection in collections {
+                    collection.set_calendars_loaded();
+                }
+            }
+
+            Ok(())
         }
 
-        fn retrieve_providers(&self) {
-            let cursor = self
-                .read_connection()
-                .query(
-                    "SELECT ?uri ?name
-                    WHERE {
+        /// Run the given work, recording its duration in the selected metrics if they are
+        /// collected.
+        fn timed<T>(
+            &self,
+            operation: impl FnOnce(&mut Metrics) -> &mut OperationMetrics,
+            work: impl FnOnce() -> T,
+        ) -> T {
+            if !self.collect_metrics.get() {
+                return work();
+            }
+
+            let start = std::time::Instant::now();
+            let result = work();
+            let mut metrics = self.metrics.get();
+            operation(&mut metrics).record(start.elapsed());
+            self.metrics.set(metrics);
+            result
+        }
+
+        /// Warn about the resources whose parent was not found once everything was loaded.
+        ///
+        /// Parents are always loaded before their children, so these point to an inconsistent
+        /// database rather than to changes arriving out of order.
+        fn report_orphaned_resources(&self) {
+            let orphaned_resources = self.orphaned_resources.borrow();
+            match orphaned_resources.len() {
+                0 => {}
+                1 => warn!("1 resource was skipped because its parent does not exist"),
+                num_orphaned => {
+                    warn!(
+                        "{num_orphaned} resources were skipped because their parent does not exist"
+                    );
+                }
+            }
+        }
+
+        /// Load the calendars of a collection and their events.
+        pub(super) fn load_collection_calendars(&self, collection: &Collection) {
+            debug!("Loading calendars of collection {}", collection.uri());
+            let result = self
+                .timed(
+                    |metrics| &mut metrics.calendar_retrievals,
+                    || self.retrieve_calendars(Some(collection)),
+                )
+                .and_then(|()| {
+                    self.timed(
+                        |metrics| &mut metrics.event_retrievals,
+                        || self.retrieve_events(Some(collection)),
+                    )
+                });
+            if let Err(err) = result {
+                warn!(
+                    "Failed to load calendars of collection {}: {err}",
+                    collection.uri()
+                );
+                return;
+            }
+            collection.set_calendars_loaded();
+            self.update_events_remaining_today();
+        }
+
+        /// A SPARQL filter restricting the given variable to the collection, if any.
+        ///
+        /// The URI of the collection must be bound as `~collection`.
+        fn collection_filter(collection: Option<&Collection>, variable: &str) -> String {
+            if collection.is_some() {
+                format!("FILTER (STR(?{variable}) = ~collection)")
+            } else {
+                String::new()
+            }
+        }
+
+        /// Count the events starting later today, and notify if the count changed.
+        pub(super) fn update_events_remaining_today(&self) {
+            let now = jiff::Zoned::now();
+            let today = now.date();
+
+            let events = self
+                .resource_pool()
+                .values()
+                .filter_map(|resource| match resource {
+                    Resource::Event(event) => Some(event.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let count = events
+                .iter()
+                .filter(|event| !self.obj().is_hidden(event))
+                .filter_map(Event::timeframe)
+                .filter(|timeframe| {
+                    let start = timeframe.start_in(now.time_zone()).0;
+                    start > now && start.date() == today
+                })
+                .count() as u32;
+
+            if self.events_remaining_today.replace(count) != count {
+                self.obj().notify_events_remaining_today();
+            }
+        }
+
+        /// Whether resources of the provider at the given URI should be loaded.
+        fn is_provider_allowed(&self, provider_uri: &str) -> bool {
+            let allowed_providers = self.allowed_providers.borrow();
+            allowed_providers.is_empty() || allowed_providers.iter().any(|uri| uri == provider_uri)
+        }
+
+        /// A SPARQL filter restricting the given variable to the allowed providers.
+        fn provider_filter(&self, variable: &str) -> String {
+            let allowed_providers = self.allowed_providers.borrow();
+            if allowed_providers.is_empty() {
+                return String::new();
+            }
+
+            let uris = allowed_providers
+                .iter()
+                .filter(|uri| {
+                    let is_valid = !uri.chars().any(|c| {
+                        c.is_whitespace()
+                            || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\')
+                    });
+                    if !is_valid {
+                        warn!("Ignoring invalid provider URI {uri}");
+                    }
+                    is_valid
+                })
+                .map(|uri| format!("<{uri}>"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("FILTER (?{variable} IN ({uris}))")
+        }
+
+        fn retrieve_providers(&self) -> Result<(), glib::Error> {
+            let account_identity_pattern =
+                PreProvider::account_identity_pattern(self.supported_features(), "?uri");
+            let provider_filter = self.provider_filter("uri");
+
+            let cancellable = self.cancellable();
+            let cursor = match self.read_connection().query(
+                &format!(
+                    "SELECT ?uri ?name ?account_identity
+                    WHERE {{
                         ?uri a ccm:Provider ;
                             ccm:providerName ?name .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .expect("Failed to retrieve providers");
+                        {account_identity_pattern}
+                        {provider_filter}
+                    }}"
+                ),
+                Some(&cancellable),
+            ) {
+                Ok(cursor) => cursor,
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => {
+                    debug!("Retrieval of providers was cancelled");
+                    return Ok(());
+                }
+                Err(err) => return Err(err),
+            };
 
-            while let Ok(true) = cursor.next(None::<&gio::Cancellable>) {
+            while let Ok(true) = cursor.next(Some(&cancellable)) {
                 let uri = cursor.string(0).expect("Query should return a URI");
                 let name = cursor.string(1).expect("Query should return a name");
-                let provider = Provider::new(&self.obj(), &uri, &name);
+                let account_identity = cursor.string(2);
+                let provider = Provider::new(&self.obj(), &uri, &name, account_identity.as_deref());
 
                 self.resource_pool()
                     .insert(uri.to_string(), Resource::Provider(provider));
 
                 info!("Found provider: uri: \"{uri}\", name: \"{name}\"");
             }
+
+            Ok(())
         }
 
-        fn retrieve_collections(&self) {
-            let cursor = self
-                .read_connection()
-                .query(
+        fn retrieve_collections(&self) -> Result<(), glib::Error> {
+            let provider_filter = self.provider_filter("provider_uri");
+
+            let cancellable = self.cancellable();
+            let cursor = match self.read_connection().query(
+                &format!(
                     "SELECT ?uri ?provider_uri ?name
-                    WHERE {
+                    WHERE {{
                         ?uri a ccm:Collection ;
                             ccm:provider ?provider_uri ;
                             ccm:collectionName ?name .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .expect("Failed to retrieve collections");
+                        {provider_filter}
+                    }}"
+                ),
+                Some(&cancellable),
+            ) {
+                Ok(cursor) => cursor,
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => {
+                    debug!("Retrieval of collections was cancelled");
+                    return Ok(());
+                }
+                Err(err) => return Err(err),
+            };
 
-            while let Ok(true) = cursor.next(None::<&gio::Cancellable>) {
+            while let Ok(true) = cursor.next(Some(&cancellable)) {
                 let uri = cursor.string(0).expect("Query should return a URI");
                 let provider_uri = cursor
                     .string(1)
@@ -177,6 +818,7 @@ mod imp {
                     self.resource_pool().get(provider_uri.as_str()).cloned()
                 else {
                     warn!("Collection \"{uri}\" has an invalid provider \"{provider_uri}\"");
+                    self.orphaned_resources.borrow_mut().push(uri.to_string());
                     continue;
                 };
 
@@ -189,35 +831,92 @@ mod imp {
 
                 info!("Found collection: uri: \"{uri}\", name: \"{name}\"");
             }
+
+            Ok(())
         }
 
-        fn retrieve_calendars(&self) {
-            let cursor = self
+        /// Retrieve the calendars of the given collection, or of every collection.
+        ///
+        /// Calendars already in the pool are skipped.
+        fn retrieve_calendars(&self, collection: Option<&Collection>) -> Result<(), glib::Error> {
+            let trash_filter = if self.supported_features().trash {
+                "FILTER NOT EXISTS { ?uri ccm:trashed true }"
+            } else {
+                ""
+            };
+            let subscription_pattern =
+                PreCalendar::subscription_pattern(self.supported_features(), "?uri");
+            let sync_token_pattern =
+                PreCalendar::sync_token_pattern(self.supported_features(), "?uri");
+            let sort_order_pattern =
+                PreCalendar::sort_order_pattern(self.supported_features(), "?uri");
+            let details_pattern = PreCalendar::details_pattern(self.supported_features(), "?uri");
+            let provider_filter = self.provider_filter("provider_uri");
+            let collection_filter = Self::collection_filter(collection, "collection_uri");
+
+            let statement = self
                 .read_connection()
-                .query(
-                    "SELECT ?uri ?collection_uri ?name ?color
-                    WHERE {
+                .query_statement(
+                    &format!(
+                        "SELECT ?uri ?collection_uri ?name ?color ?subscription_url ?last_refreshed
+                        ?sync_token ?sort_order ?source_url ?description
+                    WHERE {{
                         ?uri a ccm:Calendar ;
                             ccm:collection ?collection_uri ;
                             ccm:calendarName ?name ;
                             ccm:color ?color .
-                    }",
-                    None::<&gio::Cancellable>,
+                        ?collection_uri ccm:provider ?provider_uri .
+                        {subscription_pattern}
+                        {sync_token_pattern}
+                        {sort_order_pattern}
+                        {details_pattern}
+                        {trash_filter}
+                        {provider_filter}
+                        {collection_filter}
+                    }}"
+                    ),
+                    Some(&self.cancellable()),
                 )
-                .expect("Failed to retrieve calendars");
+                .expect("SPARQL should be valid")
+                .expect("SPARQL should be valid");
+            if let Some(collection) = collection {
+                statement.bind_string("collection", &collection.uri());
+            }
+
+            let cancellable = self.cancellable();
+            let cursor = match statement.execute(Some(&cancellable)) {
+                Ok(cursor) => cursor,
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => {
+                    debug!("Retrieval of calendars was cancelled");
+                    return Ok(());
+                }
+                Err(err) => return Err(err),
+            };
 
-            while let Ok(true) = cursor.next(None::<&gio::Cancellable>) {
+            while let Ok(true) = cursor.next(Some(&cancellable)) {
                 let uri = cursor.string(0).expect("Query should return a URI");
                 let collection_uri = cursor
                     .string(1)
                     .expect("Query should return a collection URI");
                 let name = cursor.string(2).expect("Query should return a name");
                 let color = cursor.string(3).expect("Query should return a color");
+                let subscription_url = cursor.string(4);
+                let last_refreshed = cursor.string(5).and_then(|date| date.parse::<Zoned>().ok());
+                let sync_token = cursor.string(6);
+                let sort_order = cursor.integer(7) as i32;
+                let source_url = cursor.string(8);
+                let description = cursor.string(9).unwrap_or_default();
+
+                if self.resource_pool().contains_key(uri.as_str()) {
+                    debug!("Calendar \"{uri}\" is already loaded, skipping");
+                    continue;
+                }
 
                 let Some(Resource::Collection(collection)) =
                     self.resource_pool().get(collection_uri.as_str()).cloned()
                 else {
                     warn!("Calendar \"{uri}\" has an invalid collection \"{collection_uri}\"");
+                    self.orphaned_resources.borrow_mut().push(uri.to_string());
                     continue;
                 };
 
@@ -228,6 +927,13 @@ mod imp {
                     &name,
                     color.parse().expect("Color should be a valid color string"),
                 );
+                calendar.emit_subscription_updated(
+                    subscription_url.as_deref(),
+                    last_refreshed.as_ref(),
+                );
+                calendar.emit_sync_token_updated(sync_token.as_deref());
+                calendar.emit_sort_order_updated(sort_order);
+                calendar.emit_details_updated(source_url.as_deref(), &description);
 
                 collection.add_calendar(&calendar);
                 self.resource_pool()
@@ -235,74 +941,127 @@ mod imp {
 
                 info!("Found calendar: uri: \"{uri}\", name: \"{name}\", color: \"{color}\"");
             }
+
+            Ok(())
         }
 
-        fn retrieve_events(&self) {
-            let cursor = self
+        /// Retrieve the events of the calendars of the given collection, or of every calendar.
+        ///
+        /// Events already in the pool are skipped.
+        fn retrieve_events(&self, collection: Option<&Collection>) -> Result<(), glib::Error> {
+            let trash_filter = if self.supported_features().trash {
+                "FILTER NOT EXISTS { ?calendar_uri ccm:trashed true }"
+            } else {
+                ""
+            };
+            let transparency_pattern = if self.supported_features().transparency {
+                "OPTIONAL { ?uri ccm:transparency ?transparency }"
+            } else {
+                ""
+            };
+            let exdates_pattern = PreEvent::exdates_pattern(self.supported_features(), "?uri");
+            let attendees_pattern = PreEvent::attendees_pattern(self.supported_features(), "?uri");
+            let recurrence_pattern =
+                PreEvent::recurrence_pattern(self.supported_features(), "?uri");
+            let provider_filter = self.provider_filter("provider_uri");
+            let collection_filter = Self::collection_filter(collection, "collection_uri");
+
+            let statement = self
                 .read_connection()
-                .query(
-                    "SELECT ?uri ?calendar_uri ?name ?description ?all_day ?start ?end
-                    WHERE {
+                .query_statement(
+                    &format!(
+                        "SELECT ?uri ?calendar_uri ?name ?description ?all_day ?start ?end ?color
+                        ?transparency ?exdates ?attendees ?recurrence ?recurrence_of ?recurrence_id
+                    WHERE {{
                         ?uri a ccm:Event ;
                             ccm:calendar ?calendar_uri ;
                             ccm:eventName ?name ;
-                            ccm:eventDescription ?description ;
                             ccm:eventAllDay ?all_day ;
                             ccm:eventStart ?start ;
                             ccm:eventEnd ?end .
-                    }",
-                    None::<&gio::Cancellable>,
+                        OPTIONAL {{ ?uri ccm:eventDescription ?description }}
+                        OPTIONAL {{ ?uri ccm:color ?color }}
+                        {transparency_pattern}
+                        {exdates_pattern}
+                        {attendees_pattern}
+                        {recurrence_pattern}
+                        ?calendar_uri ccm:collection ?collection_uri .
+                        ?collection_uri ccm:provider ?provider_uri .
+                        {trash_filter}
+                        {provider_filter}
+                        {collection_filter}
+                    }}"
+                    ),
+                    Some(&self.cancellable()),
                 )
-                .expect("Failed to retrieve events");
+                .expect("SPARQL should be valid")
+                .expect("SPARQL should be valid");
+            if let Some(collection) = collection {
+                statement.bind_string("collection", &collection.uri());
+            }
 
-            while let Ok(true) = cursor.next(None::<&gio::Cancellable>) {
+            let cancellable = self.cancellable();
+            let cursor = match statement.execute(Some(&cancellable)) {
+                Ok(cursor) => cursor,
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => {
+                    debug!("Retrieval of events was cancelled");
+                    return Ok(());
+                }
+                Err(err) => return Err(err),
+            };
+
+            while let Ok(true) = cursor.next(Some(&cancellable)) {
                 let uri = cursor.string(0).expect("Query should return a URI");
                 let calendar_uri = cursor
                     .string(1)
                     .expect("Query should return a calendar URI");
                 let name = cursor.string(2).expect("Query should return a name");
-                let description = cursor.string(3).expect("Query should return a description");
+                let description = cursor.string(3).unwrap_or_default();
                 let all_day = cursor.is_boolean(4);
-                let start_str = cursor.string(5).expect("Query should return a start date");
-                let end_str = cursor.string(6).expect("Query should return an end date");
-
-                let timeframe = if all_day {
-                    let start = start_str
-                        .parse::<jiff::civil::Date>()
-                        .expect("Event \"{uri}\" has an invalid start date")
-                        .into();
-                    let end = end_str
-                        .parse::<jiff::civil::Date>()
-                        .expect("Event \"{uri}\" has an invalid end date")
-                        .into();
-
-                    Timeframe::new(true, start, end)
-                } else {
-                    let start = start_str
-                        .parse::<Zoned>()
-                        .expect("Event \"{uri}\" has an invalid start date");
-                    let end = end_str
-                        .parse::<Zoned>()
-                        .expect("Event \"{uri}\" has an invalid end date");
-
-                    Timeframe::new(true, start, end)
+                let start = cursor.string(5).expect("Query should return a start date");
+                let end = cursor.string(6).expect("Query should return an end date");
+                let color = cursor.string(7).and_then(|color| color.parse().ok());
+                let transparency = EventTransparency::from_db(cursor.string(8).as_deref());
+                let exdates = PreEvent::parse_exdates(&uri, cursor.string(9).as_deref());
+                let attendees = PreEvent::parse_attendees(cursor.string(10).as_deref());
+                let recurrence = PreEvent::parse_recurrence(&uri, cursor.string(11).as_deref());
+                let recurrence_of = cursor.string(12).map(|uri| uri.to_string());
+                let recurrence_id =
+                    PreEvent::parse_recurrence_id(&uri, cursor.string(13).as_deref());
+
+                let pre_event = PreEvent {
+                    uri: uri.to_string(),
+                    calendar_uri: calendar_uri.to_string(),
+                    name: name.to_string(),
+                    description: description.to_string(),
+                    all_day,
+                    start: start.to_string(),
+                    end: end.to_string(),
+                    color,
+                    transparency,
+                    exdates,
+                    attendees,
+                    recurrence,
+                    recurrence_of,
+                    recurrence_id,
                 };
+                if !pre_event.is_valid() {
+                    continue;
+                }
+                if self.resource_pool().contains_key(uri.as_str()) {
+                    debug!("Event \"{uri}\" is already loaded, skipping");
+                    continue;
+                }
 
                 let Some(Resource::Calendar(calendar)) =
                     self.resource_pool().get(calendar_uri.as_str()).cloned()
                 else {
                     warn!("Event \"{uri}\" has an invalid calendar \"{calendar_uri}\"");
+                    self.orphaned_resources.borrow_mut().push(uri.to_string());
                     continue;
                 };
 
-                let event = Event::new(
-                    &self.obj(),
-                    &calendar,
-                    &uri,
-                    &name,
-                    &description,
-                    &timeframe,
-                );
+                let event = self.new_event(&calendar, &pre_event);
 
                 calendar.add_event(&event);
                 self.resource_pool()
@@ -312,21 +1071,192 @@ mod imp {
                     "Found event: uri: \"{uri}\", name: \"{name}\", description: \"{description}\""
                 );
             }
+
+            Ok(())
+        }
+
+        /// Create an event in the given calendar from its retrieved properties.
+        pub(super) fn new_event(&self, calendar: &Calendar, pre_event: &PreEvent) -> Event {
+            let mut pre_event = pre_event.clone();
+            pre_event.fix_malformed_times(self.malformed_time_policy.get());
+            Event::new(&self.obj(), calendar, &pre_event)
+        }
+
+        /// Retrieve the events of a single calendar, for instance after it was restored from
+        /// the trash.
+        fn retrieve_calendar_events(
+            &self,
+            calendar: &Calendar,
+            resource_pool: &mut HashMap<String, Resource>,
+        ) {
+            let statement = self
+                .read_connection()
+                .query_statement(
+                    "SELECT ?uri
+                    WHERE {
+                        ?uri a ccm:Event ;
+                            ccm:calendar ~calendar .
+                    }",
+                    Some(&self.cancellable()),
+                )
+                .expect("SPARQL should be valid")
+                .expect("SPARQL should be valid");
+            statement.bind_string("calendar", &calendar.uri());
+
+            let cancellable = self.cancellable();
+            let cursor = match statement.execute(Some(&cancellable)) {
+                Ok(cursor) => cursor,
+                Err(err) => {
+                    warn!(
+                        "Failed to retrieve events of calendar {}: {err}",
+                        calendar.uri()
+                    );
+                    return;
+                }
+            };
+
+            while let Ok(true) = cursor.next(Some(&cancellable)) {
+                let uri = cursor.string(0).expect("Query should return a URI");
+                let Ok(pre_event) = PreEvent::from_uri(
+                    self.read_connection(),
+                    self.supported_features(),
+                    &uri,
+                    &cancellable,
+                ) else {
+                    continue;
+                };
+
+                let event = self.new_event(calendar, &pre_event);
+                calendar.add_event(&event);
+                resource_pool.insert(uri.to_string(), Resource::Event(event));
+            }
+        }
+
+        /// Remove a calendar and its events from the pool and from their models.
+        fn remove_calendar(
+            &self,
+            calendar: &Calendar,
+            resource_pool: &mut HashMap<String, Resource>,
+        ) {
+            for event in calendar.events().iter::<Event>() {
+                let event = event.expect("Model should not be mutated during iteration");
+                resource_pool.remove(&event.uri());
+            }
+            resource_pool.remove(&calendar.uri());
+
+            calendar.emit_deleted();
+        }
+
+        /// Remove a collection, its calendars and their events from the pool and from their
+        /// models.
+        fn remove_collection(
+            &self,
+            collection: &Collection,
+            resource_pool: &mut HashMap<String, Resource>,
+        ) {
+            for calendar in collection.calendars().iter::<Calendar>() {
+                let calendar = calendar.expect("Model should not be mutated during iteration");
+                for event in calendar.events().iter::<Event>() {
+                    let event = event.expect("Model should not be mutated during iteration");
+                    resource_pool.remove(&event.uri());
+                }
+                resource_pool.remove(&calendar.uri());
+            }
+            resource_pool.remove(&collection.uri());
+
+            collection.emit_deleted();
+        }
+
+        /// Refresh the live searches once changes stop arriving for a short while.
+        pub(super) fn schedule_live_search_refresh(&self) {
+            if self.live_searches.borrow().is_empty() || self.live_search_refresh_pending.get() {
+                return;
+            }
+            self.live_search_refresh_pending.set(true);
+
+            let obj = self.obj().downgrade();
+            self.spawn_work(async move {
+                glib::timeout_future(std::time::Duration::from_millis(
+                    LIVE_SEARCH_DEBOUNCE_MS.into(),
+                ))
+                .await;
+                let Some(obj) = obj.upgrade() else {
+                    return;
+                };
+                obj.imp().live_search_refresh_pending.set(false);
+                obj.imp().refresh_live_searches();
+            });
+        }
+
+        /// Run the live searches again, replacing their results.
+        fn refresh_live_searches(&self) {
+            let live_searches = {
+                let mut live_searches = self.live_searches.borrow_mut();
+                live_searches.retain(|(_, _, results)| results.upgrade().is_some());
+                live_searches.clone()
+            };
+
+            for (query, options, results) in live_searches {
+                let Some(results) = results.upgrade() else {
+                    continue;
+                };
+                let new_results = self
+                    .obj()
+                    .search_events_with_options(&query, &options)
+                    .iter::<Event>()
+                    .filter_map(Result::ok)
+                    .collect::<Vec<_>>();
+                results.splice(0, results.n_items(), &new_results);
+            }
+        }
+
+        /// Notify the watchers of the resources that changed or were deleted.
+        ///
+        /// The resource pool must not be locked, as handlers may query the manager.
+        fn notify_watchers(&self, changed_uris: &[glib::GString], deleted_uris: &[glib::GString]) {
+            let (changed, deleted) = {
+                let mut watchers = self.watchers.borrow_mut();
+                watchers.retain(|_, uri_watchers| {
+                    uri_watchers.retain(|watcher| watcher.upgrade().is_some());
+                    !uri_watchers.is_empty()
+                });
+
+                let upgrade_all = |uris: &[glib::GString]| {
+                    uris.iter()
+                        .filter_map(|uri| watchers.get(uri.as_str()))
+                        .flatten()
+                        .filter_map(glib::WeakRef::upgrade)
+                        .collect::<Vec<_>>()
+                };
+
+                (upgrade_all(changed_uris), upgrade_all(deleted_uris))
+            };
+
+            for watcher in changed {
+                watcher.emit_changed();
+            }
+            for watcher in deleted {
+                watcher.emit_deleted();
+            }
         }
 
-        fn handle_notifier_events(&self, events: Vec<NotifierEvent>) {
+        fn handle_notifier_events(&self, graph: Option<&str>, events: Vec<NotifierEvent>) {
+            self.assert_owner_thread();
+
             let num_events = events.len();
+            let graph = graph.unwrap_or("the default graph");
             if num_events == 1 {
-                debug!("Starting to handle 1 event");
+                debug!("Starting to handle 1 event from {graph}");
             } else {
-                debug!("Starting to handle {num_events} events");
+                debug!("Starting to handle {num_events} events from {graph}");
             }
 
-            let mut resource_pool = self.resource_pool();
+            let resource_pool = self.resource_pool();
 
             let mut created_uris = Vec::new();
             let mut updated_uris = Vec::new();
             let mut deleted_uris = Vec::new();
+            let mut unknown_uris = Vec::new();
 
             for mut event in events {
                 match event.event_type() {
@@ -339,37 +1269,253 @@ mod imp {
                     NotifierEventType::Delete => {
                         deleted_uris.push(event.urn().unwrap());
                     }
-                    _ => {
-                        warn!("Unknown event type: {:?}", event.event_type());
+                    event_type => {
+                        let Some(uri) = event.urn() else {
+                            warn!(
+                                "Unknown event type without URI: {event_type:?} ({})",
+                                event_type.into_glib()
+                            );
+                            continue;
+                        };
+
+                        if unknown_uris.len() >= MAX_UNKNOWN_EVENT_REFETCHES {
+                            warn!(
+                                "Unknown event type: {event_type:?} ({}) for {uri}, too many to re-fetch",
+                                event_type.into_glib()
+                            );
+                            continue;
+                        }
+
+                        warn!(
+                            "Unknown event type: {event_type:?} ({}) for {uri}, re-fetching it",
+                            event_type.into_glib()
+                        );
+                        unknown_uris.push(uri);
                     }
                 }
             }
 
-            match created_uris.len() {
-                0 => {}
-                1 => {
-                    debug!("Handling 1 \"Create\" events");
-                }
-                num_create_events => {
-                    debug!("Handling {num_create_events} \"Create\" events");
+            // Resources affected by unknown events may have changed in any way, so re-fetch them.
+            // Known resources are only re-fetched through the update path of their kind, so a new
+            // kind must get one before it can be listed here.
+            for uri in unknown_uris {
+                match resource_pool.get(uri.as_str()) {
+                    Some(
+                        Resource::Provider(_)
+                        | Resource::Collection(_)
+                        | Resource::Calendar(_)
+                        | Resource::Event(_),
+                    ) => updated_uris.push(uri),
+                    None => created_uris.push(uri),
                 }
             }
-            let created_resources = created_uris
-                .into_iter()
-                .map(|uri| PreResource::from_uri(self.read_connection(), &uri).unwrap())
-                .collect::<Vec<_>>();
 
-            // Create providers
-            for pre_provider in created_resources.iter().filter_map(|pre_resource| {
-                if let PreResource::Provider(pre_provider) = pre_resource {
-                    Some(pre_provider)
-                } else {
-                    None
+            if self.batch_depth.get() > 0 {
+                debug!("Buffering {num_events} events until the batch ends");
+                let mut batched_changes = self.batched_changes.borrow_mut();
+                for (uris, kind) in [
+                    (created_uris, ResourceChangeKind::Created),
+                    (updated_uris, ResourceChangeKind::Updated),
+                    (deleted_uris, ResourceChangeKind::Deleted),
+                ] {
+                    batched_changes.extend(uris.into_iter().map(|uri| (uri, kind)));
                 }
-            }) {
-                let provider = Provider::new(&self.obj(), &pre_provider.uri, &pre_provider.name);
-                let provider_uri = pre_provider.uri.clone();
-                resource_pool.insert(provider_uri, Resource::Provider(provider));
+                return;
+            }
+
+            self.timed(
+                |metrics| &mut metrics.notifier_batches,
+                || self.apply_changes(resource_pool, created_uris, updated_uris, deleted_uris),
+            );
+
+            if num_events == 1 {
+                debug!("Finished to handle 1 event");
+            } else {
+                debug!("Finished to handle {num_events} events");
+            }
+        }
+
+        /// Start buffering the changes reported by the notifier.
+        pub(super) fn begin_batch(&self) {
+            let batch_depth = self.batch_depth.get();
+            self.batch_depth.set(batch_depth + 1);
+            if batch_depth > 0 {
+                return;
+            }
+
+            let obj = self.obj().downgrade();
+            let timeout = self.spawn(async move {
+                glib::timeout_future_seconds(MAX_BATCH_DURATION_SECS).await;
+                let Some(obj) = obj.upgrade() else {
+                    return;
+                };
+                let imp = obj.imp();
+                warn!("Batch was not ended after {MAX_BATCH_DURATION_SECS} seconds, ending it");
+                // Dropping the handle of the running timeout does not cancel it
+                imp.batch_timeout.take();
+                imp.batch_depth.set(0);
+                imp.flush_batch();
+            });
+            self.batch_timeout.replace(Some(timeout));
+        }
+
+        /// Stop buffering the changes once every batch has ended, and apply the buffered ones.
+        pub(super) fn end_batch(&self) {
+            match self.batch_depth.get() {
+                0 => warn!("Ending a batch that was not begun"),
+                1 => {
+                    self.batch_depth.set(0);
+                    if let Some(timeout) = self.batch_timeout.take() {
+                        timeout.abort();
+                    }
+                    self.flush_batch();
+                }
+                batch_depth => self.batch_depth.set(batch_depth - 1),
+            }
+        }
+
+        /// Apply the changes buffered during a batch, keeping only the last change of each
+        /// resource.
+        fn flush_batch(&self) {
+            let changes = self.batched_changes.take();
+            if changes.is_empty() {
+                return;
+            }
+            debug!("Applying {} changes buffered during a batch", changes.len());
+
+            let mut last_changes = HashMap::new();
+            let mut uris = Vec::new();
+            for (uri, kind) in changes {
+                if last_changes.insert(uri.clone(), kind).is_none() {
+                    uris.push(uri);
+                }
+            }
+
+            let resource_pool = self.resource_pool();
+            let mut created_uris = Vec::new();
+            let mut updated_uris = Vec::new();
+            let mut deleted_uris = Vec::new();
+            for uri in uris {
+                let is_known = resource_pool.contains_key(uri.as_str());
+                match (last_changes[&uri], is_known) {
+                    (ResourceChangeKind::Deleted, true) => deleted_uris.push(uri),
+                    (ResourceChangeKind::Deleted, false) => {}
+                    // Creations of known resources are handled by `apply_changes`
+                    (ResourceChangeKind::Created, _) => created_uris.push(uri),
+                    (ResourceChangeKind::Updated, true) => updated_uris.push(uri),
+                    // The creation of the resource was missed, so it is created from scratch
+                    (ResourceChangeKind::Updated, false) => created_uris.push(uri),
+                }
+            }
+
+            self.timed(
+                |metrics| &mut metrics.notifier_batches,
+                || self.apply_changes(resource_pool, created_uris, updated_uris, deleted_uris),
+            );
+        }
+
+        /// Re-read the given resources from the database and apply the changes to the pool.
+        pub(super) fn apply_changes(
+            &self,
+            mut resource_pool: MutexGuard<'_, HashMap<String, Resource>>,
+            mut created_uris: Vec<glib::GString>,
+            mut updated_uris: Vec<glib::GString>,
+            deleted_uris: Vec<glib::GString>,
+        ) {
+            // Children created before their parent are retried along with the new changes, as
+            // the parent may have been created since
+            let retried_children = self.pending_children.take();
+            for uri in retried_children.keys() {
+                let uri = glib::GString::from(uri.as_str());
+                if !created_uris.contains(&uri) && !deleted_uris.contains(&uri) {
+                    created_uris.push(uri);
+                }
+            }
+            let defer_child = |uri: &str, parent_uri: &str| {
+                let retries = retried_children.get(uri).copied().unwrap_or_default() + 1;
+                if retries > self.max_pending_retries.get() {
+                    warn!("Parent {parent_uri} of {uri} was never created, dropping {uri}");
+                } else {
+                    debug!("Parent {parent_uri} of {uri} does not exist yet, retrying later");
+                    self.pending_children
+                        .borrow_mut()
+                        .insert(uri.to_string(), retries);
+                }
+            };
+
+            // A resource reported as created may already be in the pool, after a duplicate
+            // notification or a missed deletion. Replacing it would orphan the existing object,
+            // so it is updated instead.
+            let (created_uris, already_known_uris): (Vec<_>, Vec<_>) = created_uris
+                .into_iter()
+                .partition(|uri| !resource_pool.contains_key(uri.as_str()));
+            for uri in already_known_uris {
+                debug!("Created resource {uri} is already known, updating it instead");
+                if !updated_uris.contains(&uri) {
+                    updated_uris.push(uri);
+                }
+            }
+
+            let changed_uris = created_uris
+                .iter()
+                .chain(&updated_uris)
+                .cloned()
+                .collect::<Vec<_>>();
+            let removed_uris = deleted_uris.clone();
+            let mut changes = created_uris
+                .iter()
+                .map(|uri| (uri.clone(), ResourceChangeKind::Created))
+                .chain(
+                    updated_uris
+                        .iter()
+                        .map(|uri| (uri.clone(), ResourceChangeKind::Updated)),
+                )
+                .collect::<Vec<_>>();
+
+            match created_uris.len() {
+                0 => {}
+                1 => {
+                    debug!("Handling 1 \"Create\" events");
+                }
+                num_create_events => {
+                    debug!("Handling {num_create_events} \"Create\" events");
+                }
+            }
+            let created_resources = created_uris
+                .into_iter()
+                .filter_map(|uri| {
+                    PreResource::from_uri(
+                        self.read_connection(),
+                        self.supported_features(),
+                        &uri,
+                        &self.cancellable(),
+                    )
+                    .inspect_err(|()| warn!("Created resource {uri} could not be retrieved"))
+                    .ok()
+                })
+                .collect::<Vec<_>>();
+
+            // Create providers
+            for pre_provider in created_resources.iter().filter_map(|pre_resource| {
+                if let PreResource::Provider(pre_provider) = pre_resource {
+                    Some(pre_provider)
+                } else {
+                    None
+                }
+            }) {
+                if !self.is_provider_allowed(&pre_provider.uri) {
+                    debug!("Provider {} is not allowed, skipping", pre_provider.uri);
+                    continue;
+                }
+
+                let provider = Provider::new(
+                    &self.obj(),
+                    &pre_provider.uri,
+                    &pre_provider.name,
+                    pre_provider.account_identity.as_deref(),
+                );
+                let provider_uri = pre_provider.uri.clone();
+                resource_pool.insert(provider_uri, Resource::Provider(provider));
 
                 info!(
                     "Provider created: uri: \"{}\", name: \"{}\"",
@@ -388,6 +1534,13 @@ mod imp {
                 let collection_uri = pre_collection.uri.clone();
                 let provider_uri = pre_collection.provider_uri.clone();
 
+                if !self.is_provider_allowed(&provider_uri) {
+                    debug!(
+                        "Collection {collection_uri} belongs to a provider that is not allowed, skipping"
+                    );
+                    continue;
+                }
+
                 if let Some(Resource::Provider(provider)) = resource_pool.get(&provider_uri) {
                     let collection = Collection::new(
                         &self.obj(),
@@ -396,6 +1549,8 @@ mod imp {
                         &pre_collection.name,
                     );
                     provider.add_collection(&collection);
+                    // New collections start empty, so their calendars arrive as notifier events
+                    collection.set_calendars_loaded();
                     resource_pool.insert(collection_uri, Resource::Collection(collection));
 
                     info!(
@@ -403,9 +1558,7 @@ mod imp {
                         pre_collection.uri, pre_collection.name
                     );
                 } else {
-                    warn!(
-                        "Collection {collection_uri} has provider {provider_uri} but it does not exist"
-                    );
+                    defer_child(&collection_uri, &provider_uri);
                 }
             }
 
@@ -417,10 +1570,25 @@ mod imp {
                     None
                 }
             }) {
+                if pre_calendar.trashed {
+                    debug!(
+                        "Calendar {} was created in the trash, skipping",
+                        pre_calendar.uri
+                    );
+                    continue;
+                }
+
                 let calendar_uri = pre_calendar.uri.clone();
                 let collection_uri = pre_calendar.collection_uri.clone();
 
                 if let Some(Resource::Collection(collection)) = resource_pool.get(&collection_uri) {
+                    if !collection.calendars_loaded() {
+                        debug!(
+                            "Calendar {calendar_uri} was created in collection {collection_uri}, which is not loaded yet, skipping"
+                        );
+                        continue;
+                    }
+
                     let calendar = Calendar::new(
                         &self.obj(),
                         collection,
@@ -428,6 +1596,16 @@ mod imp {
                         &pre_calendar.name,
                         pre_calendar.color,
                     );
+                    calendar.emit_subscription_updated(
+                        pre_calendar.subscription_url.as_deref(),
+                        pre_calendar.last_refreshed.as_ref(),
+                    );
+                    calendar.emit_sync_token_updated(pre_calendar.sync_token.as_deref());
+                    calendar.emit_sort_order_updated(pre_calendar.sort_order);
+                    calendar.emit_details_updated(
+                        pre_calendar.source_url.as_deref(),
+                        &pre_calendar.description,
+                    );
                     collection.add_calendar(&calendar);
                     resource_pool.insert(calendar_uri, Resource::Calendar(calendar));
 
@@ -436,9 +1614,7 @@ mod imp {
                         pre_calendar.uri, pre_calendar.name
                     );
                 } else {
-                    warn!(
-                        "Calendar {calendar_uri} has collection {collection_uri} but it does not exist"
-                    );
+                    defer_child(&calendar_uri, &collection_uri);
                 }
             }
 
@@ -453,40 +1629,8 @@ mod imp {
                 let event_uri = pre_event.uri.to_string();
                 let calendar_uri = pre_event.calendar_uri.clone();
 
-                let start_str = &pre_event.start;
-                let end_str = &pre_event.end;
-
-                let timeframe = if pre_event.all_day {
-                    let start = start_str
-                        .parse::<jiff::civil::Date>()
-                        .expect("Event \"{uri}\" has an invalid start date")
-                        .into();
-                    let end = end_str
-                        .parse::<jiff::civil::Date>()
-                        .expect("Event \"{uri}\" has an invalid end date")
-                        .into();
-
-                    Timeframe::new(true, start, end)
-                } else {
-                    let start = start_str
-                        .parse::<Zoned>()
-                        .expect("Event \"{uri}\" has an invalid start date");
-                    let end = end_str
-                        .parse::<Zoned>()
-                        .expect("Event \"{uri}\" has an invalid end date");
-
-                    Timeframe::new(true, start, end)
-                };
-
                 if let Some(Resource::Calendar(calendar)) = resource_pool.get(&calendar_uri) {
-                    let event = Event::new(
-                        &self.obj(),
-                        calendar,
-                        &pre_event.uri,
-                        &pre_event.name,
-                        &pre_event.description,
-                        &timeframe,
-                    );
+                    let event = self.new_event(calendar, pre_event);
                     calendar.add_event(&event);
                     resource_pool.insert(event_uri, Resource::Event(event));
 
@@ -495,7 +1639,13 @@ mod imp {
                         pre_event.uri, pre_event.name
                     );
                 } else {
-                    warn!("Event {event_uri} has calendar {calendar_uri} but it does not exist");
+                    if self.lazy_calendars.get() {
+                        debug!(
+                            "Event {event_uri} has calendar {calendar_uri}, which is not loaded, skipping"
+                        );
+                    } else {
+                        defer_child(&event_uri, &calendar_uri);
+                    }
                 }
             }
 
@@ -510,33 +1660,143 @@ mod imp {
             }
             let update_events = updated_uris
                 .into_iter()
-                .map(|uri| {
-                    let old = resource_pool.get(uri.as_str()).unwrap().to_owned();
-                    let new = PreResource::from_uri(self.read_connection(), &uri).unwrap();
-                    (old, new)
+                .filter_map(|uri| {
+                    let old = resource_pool.get(uri.as_str()).cloned();
+                    let new = PreResource::from_uri(
+                        self.read_connection(),
+                        self.supported_features(),
+                        &uri,
+                        &self.cancellable(),
+                    )
+                    .inspect_err(|()| warn!("Updated resource {uri} could not be retrieved"))
+                    .ok()?;
+                    Some((uri, old, new))
                 })
                 .collect::<Vec<_>>();
-            for update_event in update_events {
-                match update_event {
-                    (Resource::Provider(_old_provider), PreResource::Provider(_new_provider)) => {
-                        todo!()
+            for (uri, old, new) in update_events {
+                match (old, new) {
+                    (
+                        Some(Resource::Provider(old_provider)),
+                        PreResource::Provider(new_provider),
+                    ) => {
+                        old_provider.emit_updated(
+                            &new_provider.name,
+                            new_provider.account_identity.as_deref(),
+                        );
                     }
                     (
-                        Resource::Collection(_old_collection),
-                        PreResource::Collection(_new_collection),
+                        Some(Resource::Collection(old_collection)),
+                        PreResource::Collection(new_collection),
                     ) => {
-                        todo!()
+                        if new_collection.provider_uri != old_collection.provider().uri() {
+                            warn!(
+                                "Collection {} moved to provider {}, which is not supported",
+                                new_collection.uri, new_collection.provider_uri
+                            );
+                        }
+                        old_collection.emit_updated(&new_collection.name);
                     }
-                    (Resource::Calendar(old_calendar), PreResource::Calendar(new_calendar)) => {
+                    (
+                        Some(Resource::Calendar(old_calendar)),
+                        PreResource::Calendar(new_calendar),
+                    ) if new_calendar.trashed => {
+                        self.remove_calendar(&old_calendar, &mut resource_pool);
+
+                        info!("Calendar moved to trash: uri: \"{}\"", new_calendar.uri);
+                    }
+                    (
+                        Some(Resource::Calendar(old_calendar)),
+                        PreResource::Calendar(new_calendar),
+                    ) => {
+                        if new_calendar.collection_uri != old_calendar.collection().uri() {
+                            match resource_pool.get(&new_calendar.collection_uri) {
+                                Some(Resource::Collection(collection)) => {
+                                    old_calendar.emit_moved(collection);
+                                }
+                                _ => warn!(
+                                    "Calendar {} moved to collection {} but it does not exist",
+                                    new_calendar.uri, new_calendar.collection_uri
+                                ),
+                            }
+                        }
                         old_calendar.emit_updated(&new_calendar.name, new_calendar.color);
+                        old_calendar.emit_subscription_updated(
+                            new_calendar.subscription_url.as_deref(),
+                            new_calendar.last_refreshed.as_ref(),
+                        );
+                        old_calendar.emit_sync_token_updated(new_calendar.sync_token.as_deref());
+                        old_calendar.emit_sort_order_updated(new_calendar.sort_order);
+                        old_calendar.emit_details_updated(
+                            new_calendar.source_url.as_deref(),
+                            &new_calendar.description,
+                        );
+                    }
+                    (None, PreResource::Calendar(new_calendar)) if !new_calendar.trashed => {
+                        let calendar_uri = new_calendar.uri.clone();
+                        let collection_uri = new_calendar.collection_uri.clone();
+
+                        let Some(Resource::Collection(collection)) =
+                            resource_pool.get(&collection_uri).cloned()
+                        else {
+                            warn!(
+                                "Calendar {calendar_uri} has collection {collection_uri} but it does not exist"
+                            );
+                            continue;
+                        };
+                        if !collection.calendars_loaded() {
+                            debug!(
+                                "Calendar {calendar_uri} was restored in collection {collection_uri}, which is not loaded yet, skipping"
+                            );
+                            continue;
+                        }
+
+                        let calendar = Calendar::new(
+                            &self.obj(),
+                            &collection,
+                            &new_calendar.uri,
+                            &new_calendar.name,
+                            new_calendar.color,
+                        );
+                        calendar.emit_subscription_updated(
+                            new_calendar.subscription_url.as_deref(),
+                            new_calendar.last_refreshed.as_ref(),
+                        );
+                        calendar.emit_sync_token_updated(new_calendar.sync_token.as_deref());
+                        calendar.emit_sort_order_updated(new_calendar.sort_order);
+                        calendar.emit_details_updated(
+                            new_calendar.source_url.as_deref(),
+                            &new_calendar.description,
+                        );
+                        collection.add_calendar(&calendar);
+                        resource_pool.insert(calendar_uri, Resource::Calendar(calendar.clone()));
+                        self.retrieve_calendar_events(&calendar, &mut resource_pool);
+
+                        info!(
+                            "Calendar restored from trash: uri: \"{}\"",
+                            new_calendar.uri
+                        );
+                    }
+                    (Some(Resource::Event(old_event)), PreResource::Event(mut new_event)) => {
+                        new_event.fix_malformed_times(self.malformed_time_policy.get());
+                        if PreEvent::from_event(&old_event) == new_event {
+                            debug!("Event {} did not change, skipping update", new_event.uri);
+                            continue;
+                        }
+
+                        old_event.emit_updated(&new_event);
+                    }
+                    (None, _) => {
+                        debug!("Ignoring update of a resource that is not found locally");
                     }
-                    (Resource::Event(_old_event), PreResource::Event(_new_event)) => {}
                     _ => {
-                        todo!()
+                        warn!("Resource {uri} changed type, ignoring its update");
                     }
                 }
             }
 
+            // Skip the resources that were ignored or could not be retrieved
+            changes.retain(|(uri, _)| resource_pool.contains_key(uri.as_str()));
+
             match deleted_uris.len() {
                 0 => {}
                 1 => {
@@ -551,22 +1811,49 @@ mod imp {
                     warn!("Resource {deleted_uri} was deleted but is not found locally");
                     continue;
                 };
+                changes.push((deleted_uri.clone(), ResourceChangeKind::Deleted));
                 match resource {
-                    Resource::Provider(_provider) => todo!(),
-                    Resource::Collection(_collection) => todo!(),
+                    Resource::Provider(provider) => {
+                        let collections = provider
+                            .collections()
+                            .iter::<Collection>()
+                            .collect::<Vec<_>>();
+                        for collection in collections {
+                            let collection =
+                                collection.expect("Model should not be mutated during iteration");
+                            self.remove_collection(&collection, &mut resource_pool);
+                        }
+                        resource_pool.remove(deleted_uri.as_str());
+
+                        info!("Provider deleted: uri: \"{deleted_uri}\"");
+                    }
+                    Resource::Collection(collection) => {
+                        self.remove_collection(&collection, &mut resource_pool);
+
+                        info!("Collection deleted: uri: \"{deleted_uri}\"");
+                    }
                     Resource::Calendar(calendar) => {
-                        // TODO: Emit deleted for events too
-                        calendar.emit_deleted();
+                        self.remove_calendar(&calendar, &mut resource_pool);
+
+                        info!("Calendar deleted: uri: \"{deleted_uri}\"");
+                    }
+                    Resource::Event(event) => {
+                        resource_pool.remove(deleted_uri.as_str());
+                        event.emit_deleted();
+
+                        info!("Event deleted: uri: \"{deleted_uri}\"");
                     }
-                    Resource::Event(_event) => todo!(),
                 }
             }
 
-            if num_events == 1 {
-                debug!("Finished to handle 1 event");
-            } else {
-                debug!("Finished to handle {num_events} events");
+            drop(resource_pool);
+            for (uri, kind) in changes {
+                self.obj()
+                    .emit_by_name::<()>("resource-changed", &[&uri.as_str(), &kind]);
             }
+            self.notify_watchers(&changed_uris, &removed_uris);
+            self.update_events_remaining_today();
+            self.schedule_live_search_refresh();
         }
     }
 }
@@ -580,129 +1867,2053 @@ impl Manager {
         glib::Object::builder().build()
     }
 
+    /// Create a manager, opening its connections asynchronously and resolving once the initial
+    /// load of resources is done.
+    ///
+    /// Unlike [`Manager::new`], which blocks while connecting and panics if the services are not
+    /// available, this returns the error of the connection that failed, or of the initial load.
+    pub async fn new_future() -> Result<Self, glib::Error> {
+        let read_connection =
+            SparqlConnection::bus_new_future("io.gitlab.TitouanReal.CcmRead", None, None).await?;
+        let write_connection = DBusProxy::for_bus_future(
+            BusType::Session,
+            DBusProxyFlags::NONE,
+            None,
+            "io.gitlab.TitouanReal.CcmWrite",
+            "/io/gitlab/TitouanReal/CcmWrite/Provider",
+            "io.gitlab.TitouanReal.CcmWrite.Provider",
+        )
+        .await?;
+        let session_bus = gio::bus_get_future(BusType::Session).await?;
+        let supported_features = FeatureSet::probe_future(&read_connection).await;
+        let notifier = read_connection.create_notifier().ok_or_else(|| {
+            glib::Error::new(
+                gio::IOErrorEnum::Failed,
+                "Failed to create a notifier for the database",
+            )
+        })?;
+
+        let obj: Self = glib::Object::builder()
+            .property("read_connection", read_connection)
+            .property("write_connection", write_connection)
+            .property("session_bus", session_bus)
+            .property("supported_features", supported_features)
+            .property("notifier", notifier)
+            .build();
+
+        // The initial load is spawned on construction, so it cannot have finished yet
+        let loaded = Rc::new(RefCell::new((false, None::<Waker>)));
+        let handler = obj.connect_loading_finished(clone!(
+            #[strong]
+            loaded,
+            move |_| {
+                let mut loaded = loaded.borrow_mut();
+                loaded.0 = true;
+                if let Some(waker) = loaded.1.take() {
+                    waker.wake();
+                }
+            }
+        ));
+        std::future::poll_fn(|cx| {
+            let mut loaded = loaded.borrow_mut();
+            if loaded.0 {
+                Poll::Ready(())
+            } else {
+                loaded.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await;
+        obj.disconnect(handler);
+
+        match obj.imp().load_error.take() {
+            Some(err) => Err(err),
+            None => Ok(obj),
+        }
+    }
+
+    /// Create a manager that spawns its work on the given main context.
+    ///
+    /// Retrievals and refreshes only run when that context is iterated. [`Manager::new`] uses the
+    /// thread-default main context.
+    pub fn with_main_context(main_context: &glib::MainContext) -> Self {
+        glib::Object::builder()
+            .property("main_context", main_context)
+            .build()
+    }
+
+    /// Create a manager that only follows changes made to the given graph.
+    ///
+    /// Notifier events from other graphs are ignored, which avoids re-reading resources written
+    /// by unrelated providers.
+    pub fn for_graph(graph: &str) -> Self {
+        glib::Object::builder().property("graph", graph).build()
+    }
+
+    /// Create a manager that only loads the resources of the given providers.
+    ///
+    /// Resources of other providers are skipped, both when loading and when they are created
+    /// later on.
+    pub fn for_providers(provider_uris: &[&str]) -> Self {
+        glib::Object::builder()
+            .property("allowed_providers", glib::StrV::from(provider_uris))
+            .build()
+    }
+
+    /// Create a manager that only loads the calendars of a collection when
+    /// [`Collection::ensure_calendars_loaded`] is called, along with their events.
+    ///
+    /// This keeps the initial load short for accounts with many collections. Calendars created
+    /// in a collection that is not loaded yet are skipped, and found when it is loaded.
+    pub fn with_lazy_calendars() -> Self {
+        glib::Object::builder()
+            .property("lazy_calendars", true)
+            .build()
+    }
+
+    /// Create a manager that only reads from the database.
+    ///
+    /// No write connection is opened, and every write method returns [`WriteError::ReadOnly`].
+    pub fn new_read_only() -> Self {
+        glib::Object::builder().property("read_only", true).build()
+    }
+
+    /// The child model of an item of the resource hierarchy, for building a tree model.
+    ///
+    /// Providers have their collections as children, collections have their calendars, and
+    /// calendars have none. This crate does not depend on GTK, so the tree is built by passing
+    /// this function to `gtk::TreeListModel::new` along with [`Manager::collections_model`] as
+    /// root. The child models are the live models of the resources, so the tree stays up to date
+    /// and children are only created when a row is expanded.
+    pub fn tree_child_model(item: &glib::Object) -> Option<gio::ListModel> {
+        if let Some(provider) = item.downcast_ref::<Provider>() {
+            Some(provider.collections().upcast())
+        } else {
+            item.downcast_ref::<Collection>()
+                .map(|collection| collection.clone().upcast())
+        }
+    }
+
     pub fn find_resource(&self, uri: &str) -> Option<Resource> {
         self.imp().resource_pool().get(uri).cloned()
     }
 
-    pub(crate) fn create_calendar(&self, collection_uri: &str, name: &str, color: RGBA) {
-        // TODO: dispatch to relevant provider instead
-        self.imp()
-            .write_connection()
-            .call_sync(
-                "CreateCalendar",
-                Some(&(collection_uri, name, &color.to_string()).to_variant()),
-                DBusCallFlags::NONE,
-                -1,
-                None::<&gio::Cancellable>,
-            )
-            .unwrap();
+    /// Connect to the signal emitted once the providers, collections and calendars are loaded.
+    ///
+    /// It is emitted before the events are loaded, and always before the `loading-finished`
+    /// signal, so the calendar hierarchy can be shown while events are still being retrieved.
+    pub fn connect_structure_ready<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "structure-ready",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
     }
 
-    pub(crate) fn update_calendar(&self, uri: &str, name: Option<&str>, color: Option<RGBA>) {
-        // TODO: dispatch to relevant provider instead
-        if let Some(name) = name {
-            self.imp()
-                .write_connection()
-                .call_sync(
-                    "UpdateCalendarName",
-                    Some(&(uri, name).to_variant()),
-                    DBusCallFlags::NONE,
-                    -1,
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap();
-        }
-        if let Some(color) = color {
-            self.imp()
-                .write_connection()
-                .call_sync(
-                    "UpdateCalendarColor",
-                    Some(&(uri, color.to_string()).to_variant()),
-                    DBusCallFlags::NONE,
-                    -1,
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap();
-        }
+    /// Connect to the signal emitted once every resource, including events, is loaded.
+    ///
+    /// It is always emitted after the `structure-ready` signal.
+    pub fn connect_loading_finished<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "loading-finished",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
     }
 
-    pub(crate) fn delete_calendar(&self, uri: &str) {
-        // TODO: dispatch to relevant provider instead
-        self.imp()
-            .write_connection()
-            .call_sync(
-                "DeleteCalendar",
-                Some(&(uri,).to_variant()),
-                DBusCallFlags::NONE,
-                -1,
-                None::<&gio::Cancellable>,
-            )
-            .unwrap();
+    /// Connect to the signal emitted for each resource created, updated or deleted in the database.
+    ///
+    /// It is emitted alongside the signals of the resources themselves, once a batch of changes
+    /// is applied, in the order the changes were applied: creations, then updates, then deletions.
+    pub fn connect_resource_changed<F: Fn(&Self, &str, ResourceChangeKind) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "resource-changed",
+            true,
+            closure_local!(|obj: Self, uri: String, kind: ResourceChangeKind| {
+                f(&obj, &uri, kind);
+            }),
+        )
     }
 
-    pub(crate) fn create_event(&self, calendar_uri: &str, name: &str, description: &str) {
-        // TODO: dispatch to relevant provider instead
-        self.imp()
-            .write_connection()
-            .call_sync(
-                "CreateEvent",
-                Some(&(calendar_uri, name, description).to_variant()),
-                DBusCallFlags::NONE,
-                -1,
-                None::<&gio::Cancellable>,
+    /// Re-read a single resource from the database, and apply the changes to it.
+    ///
+    /// This is useful when a resource is suspected to be out of date. The resource is created if
+    /// it is new, updated if it changed, and treated as deleted if it no longer exists in the
+    /// database. Fails if the database could not be queried, or if the resource exists but could
+    /// not be read.
+    pub fn reload(&self, uri: &str) -> Result<(), PreResourceError> {
+        self.imp().assert_owner_thread();
+
+        let statement = self
+            .imp()
+            .read_connection()
+            .query_statement("ASK { ~uri a ?type }", Some(&self.imp().cancellable()))
+            .expect("SPARQL should be valid")
+            .expect("SPARQL should be valid");
+        statement.bind_string("uri", uri);
+
+        let cancellable = self.imp().cancellable();
+        let cursor = statement.execute(Some(&cancellable))?;
+        let exists = cursor.next(Some(&cancellable))? && cursor.is_boolean(0);
+        if exists
+            && PreResource::from_uri(
+                self.imp().read_connection(),
+                self.imp().supported_features(),
+                uri,
+                &cancellable,
             )
-            .unwrap();
-    }
+            .is_err()
+        {
+            return Err(PreResourceError::Unreadable);
+        }
 
-    pub fn search_events(&self, query: &str) -> ListStore {
-        if query.is_empty() {
-            return ListStore::new::<Event>();
+        let resource_pool = self.imp().resource_pool();
+        let is_known = resource_pool.contains_key(uri);
+        let uri = glib::GString::from(uri);
+        match (exists, is_known) {
+            (true, true) => {
+                self.imp()
+                    .apply_changes(resource_pool, Vec::new(), vec![uri], Vec::new());
+            }
+            (true, false) => {
+                self.imp()
+                    .apply_changes(resource_pool, vec![uri], Vec::new(), Vec::new());
+            }
+            (false, true) => {
+                self.imp()
+                    .apply_changes(resource_pool, Vec::new(), Vec::new(), vec![uri]);
+            }
+            (false, false) => {
+                debug!("Resource {uri} to reload does not exist");
+            }
         }
 
+        Ok(())
+    }
+
+    /// Whether the resource at the given URI is held by this manager.
+    ///
+    /// Unlike [`Manager::find_resource`], the resource is not cloned.
+    pub fn contains(&self, uri: &str) -> bool {
+        self.imp().resource_pool().contains_key(uri)
+    }
+
+    /// Whether a resource exists at the given URI in the database, even if this manager does not
+    /// hold it.
+    ///
+    /// Returns `false` if the query fails, or if it is cancelled with
+    /// [`Manager::cancel_pending_queries`].
+    pub async fn exists_in_db(&self, uri: &str) -> bool {
         let statement = self
             .imp()
             .read_connection()
             .query_statement(
-                "SELECT ?uri
-                WHERE {
-                    ?uri a ccm:Event ;
-                        fts:match ~query .
+                "ASK {
+                    ~uri a ?type .
+                    FILTER (?type IN (ccm:Provider, ccm:Collection, ccm:Calendar, ccm:Event))
                 }",
-                None::<&gio::Cancellable>,
+                Some(&self.imp().cancellable()),
             )
             .expect("SPARQL should be valid")
             .expect("SPARQL should be valid");
-        statement.bind_string("query", query);
-
-        let cursor = match statement.execute(None::<&gio::Cancellable>) {
-            Ok(cursor) => cursor,
-            Err(err) => {
-                warn!("Failed to search events: {err:?}");
-                return ListStore::new::<Event>();
+        statement.bind_string("uri", uri);
+
+        let cancellable = self.imp().cancellable();
+        let cursor = match gio::CancellableFuture::new(
+            statement.execute_future(),
+            cancellable.clone(),
+        )
+        .await
+        {
+            Ok(Ok(cursor)) => cursor,
+            Ok(Err(err)) => {
+                warn!("Failed to check whether {uri} exists: {err:?}");
+                return false;
             }
+            Err(gio::Cancelled) => return false,
         };
 
-        let search_results = ListStore::new::<Event>();
+        match gio::CancellableFuture::new(cursor.next_future(), cancellable).await {
+            Ok(Ok(true)) => cursor.is_boolean(0),
+            Ok(Ok(false)) | Err(gio::Cancelled) => false,
+            Ok(Err(err)) => {
+                warn!("Failed to check whether {uri} exists: {err:?}");
+                false
+            }
+        }
+    }
 
-        while let Ok(true) = cursor.next(None::<&gio::Cancellable>) {
-            let uri = cursor.string(0).expect("Query should return a URI");
+    /// Find the provider owning the resource at the given URI.
+    ///
+    /// For a provider URI, the provider itself is returned.
+    pub fn owning_provider(&self, uri: &str) -> Option<Provider> {
+        match self.find_resource(uri)? {
+            Resource::Provider(provider) => Some(provider),
+            Resource::Collection(collection) => Some(collection.provider()),
+            Resource::Calendar(calendar) => Some(calendar.collection().provider()),
+            Resource::Event(event) => Some(event.calendar().collection().provider()),
+        }
+    }
 
-            let Some(Resource::Event(event)) =
-                self.imp().resource_pool().get(uri.as_str()).cloned()
-            else {
-                warn!("Event \"{uri}\" is not in resource pool");
-                continue;
-            };
+    /// Ask the backend to create a new provider.
+    ///
+    /// The URI of the new provider is returned, and the provider appears in the manager once the
+    /// backend reports its creation.
+    pub fn create_provider(&self, name: &str, kind: ProviderKind) -> Result<String, WriteError> {
+        self.validate_name(name)?;
+        self.imp()
+            .call_create("CreateProvider", &(name, kind.as_str()).to_variant())
+    }
 
-            search_results.append(&event);
+    /// Load the calendars of a collection and their events.
+    pub(crate) fn load_collection_calendars(&self, collection: &Collection) {
+        self.imp().load_collection_calendars(collection);
+    }
+
+    /// Check that a name can be written, according to [`Manager::max_name_length`].
+    pub(crate) fn validate_name(&self, name: &str) -> Result<(), ValidationError> {
+        ValidationError::check_name(name, self.max_name_length() as usize)
+    }
+
+    pub(crate) fn create_calendar(
+        &self,
+        collection_uri: &str,
+        new_calendar: &NewCalendar,
+    ) -> Result<String, WriteError> {
+        self.validate_name(&new_calendar.name)?;
+
+        // TODO: dispatch to relevant provider instead
+        self.imp().call_create(
+            "CreateCalendar",
+            &(
+                collection_uri,
+                &new_calendar.name,
+                &new_calendar.color.to_string(),
+            )
+                .to_variant(),
+        )
+    }
+
+    pub(crate) fn subscribe_ical(
+        &self,
+        collection_uri: &str,
+        url: &str,
+        refresh: jiff::Span,
+    ) -> Result<String, WriteError> {
+        let refresh_seconds = refresh
+            .total((jiff::Unit::Second, jiff::civil::date(2000, 1, 1)))
+            .map_err(|err| {
+                glib::Error::new(
+                    gio::IOErrorEnum::InvalidArgument,
+                    &format!("Invalid refresh interval {refresh}: {err}"),
+                )
+            })? as u64;
+
+        // TODO: dispatch to relevant provider instead
+        self.imp().call_create(
+            "SubscribeIcal",
+            &(collection_uri, url, refresh_seconds).to_variant(),
+        )
+    }
+
+    pub(crate) fn update_calendar(
+        &self,
+        uri: &str,
+        name: Option<&str>,
+        color: Option<RGBA>,
+    ) -> Result<(), WriteError> {
+        if let Some(name) = name {
+            self.validate_name(name)?;
         }
 
-        search_results
+        // TODO: dispatch to relevant provider instead
+        if let (Some(name), Some(color)) = (name, color) {
+            let fields = HashMap::from([
+                ("name".to_string(), name.to_variant()),
+                ("color".to_string(), color.to_string().to_variant()),
+            ]);
+
+            match self
+                .imp()
+                .call_write("UpdateCalendar", &(uri, fields).to_variant())
+            {
+                Ok(_) => return Ok(()),
+                Err(WriteError::DBus(err)) if err.matches(gio::DBusError::UnknownMethod) => {
+                    debug!("Write service lacks UpdateCalendar, updating fields one by one");
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if let Some(name) = name {
+            self.imp()
+                .call_write("UpdateCalendarName", &(uri, name).to_variant())?;
+        }
+        if let Some(color) = color {
+            self.imp().call_write(
+                "UpdateCalendarColor",
+                &(uri, color.to_string()).to_variant(),
+            )?;
+        }
+
+        Ok(())
     }
-}
 
-impl Default for Manager {
-    fn default() -> Self {
-        Self::new()
+    /// Ask the backend to update a calendar asynchronously, retrying transient failures.
+    ///
+    /// See [`Manager::update_calendar`].
+    pub(crate) async fn update_calendar_future(
+        &self,
+        uri: &str,
+        name: Option<&str>,
+        color: Option<RGBA>,
+    ) -> Result<(), WriteError> {
+        if let Some(name) = name {
+            self.validate_name(name)?;
+        }
+
+        // TODO: dispatch to relevant provider instead
+        if let (Some(name), Some(color)) = (name, color) {
+            let fields = HashMap::from([
+                ("name".to_string(), name.to_variant()),
+                ("color".to_string(), color.to_string().to_variant()),
+            ]);
+
+            match self
+                .imp()
+                .call_write_future("UpdateCalendar", &(uri, fields).to_variant())
+                .await
+            {
+                Ok(_) => return Ok(()),
+                Err(WriteError::DBus(err)) if err.matches(gio::DBusError::UnknownMethod) => {
+                    debug!("Write service lacks UpdateCalendar, updating fields one by one");
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if let Some(name) = name {
+            self.imp()
+                .call_write_future("UpdateCalendarName", &(uri, name).to_variant())
+                .await?;
+        }
+        if let Some(color) = color {
+            self.imp()
+                .call_write_future(
+                    "UpdateCalendarColor",
+                    &(uri, color.to_string()).to_variant(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that the calendar at the given URI is still named `last_known_name` in the database.
+    pub(crate) fn check_calendar_name(
+        &self,
+        uri: &str,
+        last_known_name: &str,
+    ) -> Result<(), WriteError> {
+        let Ok(pre_calendar) = PreCalendar::from_uri(
+            self.imp().read_connection(),
+            self.imp().supported_features(),
+            uri,
+            &self.imp().cancellable(),
+        ) else {
+            warn!("Calendar {uri} could not be read again, it may have been deleted");
+            return Err(WriteError::Conflict);
+        };
+
+        if let Some(Resource::Calendar(calendar)) = self.imp().resource_pool().get(uri)
+            && calendar.name() != pre_calendar.name
+        {
+            warn!("Local copy of calendar {uri} is stale, another client renamed it");
+        }
+
+        if pre_calendar.name != last_known_name {
+            warn!(
+                "Calendar {uri} was renamed to \"{}\" by another client, not overwriting it",
+                pre_calendar.name
+            );
+            return Err(WriteError::Conflict);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn update_event_color(
+        &self,
+        uri: &str,
+        color: Option<RGBA>,
+    ) -> Result<(), WriteError> {
+        let color = color.map(|color| color.to_string()).unwrap_or_default();
+
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write("UpdateEventColor", &(uri, color).to_variant())?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn update_event_color_future(
+        &self,
+        uri: &str,
+        color: Option<RGBA>,
+    ) -> Result<(), WriteError> {
+        let color = color.map(|color| color.to_string()).unwrap_or_default();
+
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write_future("UpdateEventColor", &(uri, color).to_variant())
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn update_event_participation(
+        &self,
+        uri: &str,
+        email: &str,
+        status: ParticipationStatus,
+    ) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp().call_write(
+            "UpdateEventParticipation",
+            &(uri, email, status.as_str()).to_variant(),
+        )?;
+
+        Ok(())
+    }
+
+    pub(crate) fn update_calendar_trashed(
+        &self,
+        uri: &str,
+        trashed: bool,
+    ) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write("UpdateCalendarTrashed", &(uri, trashed).to_variant())?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn update_calendar_trashed_future(
+        &self,
+        uri: &str,
+        trashed: bool,
+    ) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write_future("UpdateCalendarTrashed", &(uri, trashed).to_variant())
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn update_calendar_collection(
+        &self,
+        uri: &str,
+        collection_uri: &str,
+    ) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp().call_write(
+            "UpdateCalendarCollection",
+            &(uri, collection_uri).to_variant(),
+        )?;
+
+        Ok(())
+    }
+
+    pub(crate) fn update_calendar_sort_order(
+        &self,
+        uri: &str,
+        sort_order: i32,
+    ) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write("UpdateCalendarSortOrder", &(uri, sort_order).to_variant())?;
+
+        Ok(())
+    }
+
+    /// Ask the backend to update the positions of several calendars, in a single call if it
+    /// supports it.
+    ///
+    /// Backends without bulk updates get one call per calendar, and then updates stop at the
+    /// first failure, leaving the calendars updated so far in their new position.
+    pub(crate) fn update_calendar_sort_orders(
+        &self,
+        sort_orders: &[(String, i32)],
+    ) -> Result<(), WriteError> {
+        if sort_orders.is_empty() {
+            return Ok(());
+        }
+
+        // TODO: dispatch to relevant provider instead
+        match self.imp().call_write(
+            "UpdateCalendarSortOrders",
+            &(sort_orders.to_vec(),).to_variant(),
+        ) {
+            Ok(_) => return Ok(()),
+            Err(WriteError::DBus(err)) if err.matches(gio::DBusError::UnknownMethod) => {
+                debug!(
+                    "Write service lacks UpdateCalendarSortOrders, updating calendars one by one"
+                );
+            }
+            Err(err) => return Err(err),
+        }
+
+        for (uri, sort_order) in sort_orders {
+            self.update_calendar_sort_order(uri, *sort_order)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_calendar(&self, uri: &str) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write("DeleteCalendar", &(uri,).to_variant())?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn delete_calendar_future(&self, uri: &str) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write_future("DeleteCalendar", &(uri,).to_variant())
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_event(&self, uri: &str) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp().call_write("DeleteEvent", &(uri,).to_variant())?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn delete_event_future(&self, uri: &str) -> Result<(), WriteError> {
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_write_future("DeleteEvent", &(uri,).to_variant())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Ask the backend to create a deleted event again, returning its new URI.
+    ///
+    /// Backends without `RestoreEvent` get the event created again with its name, description,
+    /// time frame and color only.
+    pub(crate) fn restore_event(&self, event: &DeletedEvent) -> Result<String, WriteError> {
+        let color = event
+            .color
+            .map(|color| color.to_string())
+            .unwrap_or_default();
+        let exdates = event
+            .exdates
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let attendees = event
+            .attendees
+            .iter()
+            .map(|attendee| (attendee.email.clone(), attendee.status.as_str().to_string()))
+            .collect::<Vec<_>>();
+        let recurrence = event
+            .recurrence
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let recurrence_of = event.recurrence_of.clone().unwrap_or_default();
+        let recurrence_id = event
+            .recurrence_id
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+
+        // TODO: dispatch to relevant provider instead
+        match self.imp().call_create(
+            "RestoreEvent",
+            &(
+                &event.calendar_uri,
+                &event.name,
+                &event.description,
+                &event.timeframe,
+                color,
+                event.transparency.as_str(),
+                exdates,
+                attendees,
+                recurrence,
+                recurrence_of,
+                recurrence_id,
+            )
+                .to_variant(),
+        ) {
+            Ok(uri) => return Ok(uri),
+            Err(WriteError::DBus(err)) if err.matches(gio::DBusError::UnknownMethod) => {
+                warn!(
+                    "Write service lacks RestoreEvent, event {} is restored without its \
+                    transparency, exception dates, attendees and recurrence",
+                    event.uri
+                );
+            }
+            Err(err) => return Err(err),
+        }
+
+        let uri = self.create_event(
+            &event.calendar_uri,
+            &NewEvent {
+                name: event.name.clone(),
+                description: event.description.clone(),
+                timeframe: event.timeframe.clone(),
+                location: String::new(),
+            },
+        )?;
+        if event.color.is_some() {
+            self.update_event_color(&uri, event.color)?;
+        }
+
+        Ok(uri)
+    }
+
+    /// Ask the backend to delete the events at the given URIs, in a single call if it supports
+    /// it.
+    ///
+    /// Backends without bulk deletion get one call per event, and then deletions stop at the
+    /// first failure. The events are removed from their calendars once the backend reports their
+    /// deletion.
+    pub fn delete_events(&self, uris: &[&str]) -> Result<(), WriteError> {
+        if uris.is_empty() {
+            return Ok(());
+        }
+
+        // TODO: dispatch to relevant provider instead
+        match self
+            .imp()
+            .call_write("DeleteEvents", &(uris.to_vec(),).to_variant())
+        {
+            Ok(_) => return Ok(()),
+            Err(WriteError::DBus(err)) if err.matches(gio::DBusError::UnknownMethod) => {
+                debug!("Write service lacks DeleteEvents, deleting events one by one");
+            }
+            Err(err) => return Err(err),
+        }
+
+        for uri in uris {
+            self.delete_event(uri)?;
+        }
+
+        Ok(())
+    }
+
+    /// Ask the backend to delete the events in `remove`, keeping `keep`.
+    ///
+    /// This is meant to clean up the duplicates found by [`Calendar::find_duplicates`]. `keep` is
+    /// never deleted, even if it is listed in `remove`. Deletions stop at the first failure.
+    pub fn merge_events(&self, keep: &Event, remove: &[Event]) -> Result<(), WriteError> {
+        for event in remove.iter().filter(|event| *event != keep) {
+            self.delete_event(&event.uri())?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn create_event(
+        &self,
+        calendar_uri: &str,
+        new_event: &NewEvent,
+    ) -> Result<String, WriteError> {
+        self.validate_name(&new_event.name)?;
+
+        // TODO: dispatch to relevant provider instead
+        self.imp().call_create(
+            "CreateEvent",
+            &Self::create_event_parameters(calendar_uri, new_event),
+        )
+    }
+
+    /// Ask the backend to create a new event in the given calendar asynchronously, retrying
+    /// transient failures according to the write retry policy.
+    pub(crate) async fn create_event_future(
+        &self,
+        calendar_uri: &str,
+        new_event: &NewEvent,
+    ) -> Result<String, WriteError> {
+        self.validate_name(&new_event.name)?;
+
+        // TODO: dispatch to relevant provider instead
+        self.imp()
+            .call_create_future(
+                "CreateEvent",
+                &Self::create_event_parameters(calendar_uri, new_event),
+            )
+            .await
+    }
+
+    /// The parameters of the `CreateEvent` method of the write service.
+    fn create_event_parameters(calendar_uri: &str, new_event: &NewEvent) -> glib::Variant {
+        (
+            calendar_uri,
+            &new_event.name,
+            &new_event.description,
+            &new_event.timeframe,
+            &new_event.location,
+        )
+            .to_variant()
+    }
+
+    /// Remember a write that was just made, so that it can be undone.
+    ///
+    /// This forgets the writes that were undone, and the oldest write once the undo stack is full.
+    pub(crate) fn record_undo(&self, action: UndoAction) {
+        let mut undo_stack = self.imp().undo_stack.borrow_mut();
+        if undo_stack.len() == MAX_UNDO_ACTIONS {
+            undo_stack.pop_front();
+        }
+        undo_stack.push_back(action);
+        self.imp().redo_stack.borrow_mut().clear();
+    }
+
+    /// Revert the last write made through the resources of this manager.
+    ///
+    /// Returns `false` if there is nothing to undo. If the write fails, the action stays on the
+    /// undo stack.
+    pub fn undo(&self) -> Result<bool, WriteError> {
+        self.imp().assert_owner_thread();
+
+        let Some(mut action) = self.imp().undo_stack.borrow_mut().pop_back() else {
+            return Ok(false);
+        };
+
+        match action.inverse().apply(self) {
+            Ok(created_uri) => {
+                if let Some(uri) = created_uri {
+                    action.set_created_uri(uri);
+                }
+            }
+            Err(err) => {
+                self.imp().undo_stack.borrow_mut().push_back(action);
+                return Err(err);
+            }
+        }
+
+        self.imp().redo_stack.borrow_mut().push(action);
+        Ok(true)
+    }
+
+    /// Make again the last write reverted by [`Manager::undo`].
+    ///
+    /// Returns `false` if there is nothing to redo. If the write fails, the action stays on the
+    /// redo stack.
+    pub fn redo(&self) -> Result<bool, WriteError> {
+        self.imp().assert_owner_thread();
+
+        let Some(mut action) = self.imp().redo_stack.borrow_mut().pop() else {
+            return Ok(false);
+        };
+
+        match action.apply(self) {
+            Ok(created_uri) => {
+                if let Some(uri) = created_uri {
+                    action.set_created_uri(uri);
+                }
+            }
+            Err(err) => {
+                self.imp().redo_stack.borrow_mut().push(action);
+                return Err(err);
+            }
+        }
+
+        self.imp().undo_stack.borrow_mut().push_back(action);
+        Ok(true)
+    }
+
+    /// Retrieve the calendars that were moved to the trash.
+    ///
+    /// These calendars are not part of the resource pool, and their events are not loaded. They
+    /// can be brought back with [`Calendar::restore`].
+    pub fn trashed_calendars(&self) -> ListStore {
+        let trashed_calendars = ListStore::new::<Calendar>();
+
+        if !self.supported_features().trash {
+            return trashed_calendars;
+        }
+
+        let cursor = match self.imp().read_connection().query(
+            "SELECT ?uri ?collection_uri ?name ?color
+            WHERE {
+                ?uri a ccm:Calendar ;
+                    ccm:collection ?collection_uri ;
+                    ccm:calendarName ?name ;
+                    ccm:color ?color ;
+                    ccm:trashed true .
+            }",
+            Some(&self.imp().cancellable()),
+        ) {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                warn!("Failed to retrieve trashed calendars: {err:?}");
+                return trashed_calendars;
+            }
+        };
+
+        while let Ok(true) = cursor.next(Some(&self.imp().cancellable())) {
+            let uri = cursor.string(0).expect("Query should return a URI");
+            let collection_uri = cursor
+                .string(1)
+                .expect("Query should return a collection URI");
+            let name = cursor.string(2).expect("Query should return a name");
+            let color = cursor.string(3).expect("Query should return a color");
+
+            let Some(Resource::Collection(collection)) = self.find_resource(&collection_uri) else {
+                warn!("Calendar \"{uri}\" has an invalid collection \"{collection_uri}\"");
+                continue;
+            };
+
+            let Ok(color) = color.parse() else {
+                warn!("Calendar \"{uri}\" has an invalid color \"{color}\"");
+                continue;
+            };
+
+            trashed_calendars.append(&Calendar::new(self, &collection, &uri, &name, color));
+        }
+
+        trashed_calendars
+    }
+
+    /// Suggest a color for a new calendar.
+    ///
+    /// The first color of the built-in palette that no calendar uses is returned. Once the palette
+    /// is exhausted, a color is derived from the number of calendars instead.
+    pub fn suggest_calendar_color(&self) -> RGBA {
+        let used_colors: Vec<RGBA> = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Calendar(calendar) => calendar.color(),
+                _ => None,
+            })
+            .collect();
+
+        if let Some(color) = CALENDAR_PALETTE
+            .iter()
+            .map(|color| RGBA::parse(*color).expect("Palette colors should be valid"))
+            .find(|color| !used_colors.contains(color))
+        {
+            return color;
+        }
+
+        // Spread the hues using the golden angle, so consecutive suggestions stay distinct
+        let hue = (used_colors.len() as f32 * 137.508) % 360.;
+        let (red, green, blue) = hsv_to_rgb(hue, 0.65, 0.85);
+        RGBA::new(red, green, blue, 1.)
+    }
+
+    /// Find the pairs of calendars whose colors are closer than `threshold`, so the user can be
+    /// warned that they look alike.
+    ///
+    /// The distance is the Euclidean distance between the red, green and blue components, from 0
+    /// for identical colors to about 1.73 between black and white. Around 0.1 is a good threshold
+    /// for colors hard to tell apart. Pairs are sorted by the URIs of their calendars.
+    pub fn color_collisions(&self, threshold: f32) -> Vec<(Calendar, Calendar)> {
+        let mut calendars = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Calendar(calendar) => Some(calendar.clone()),
+                _ => None,
+            })
+            .filter_map(|calendar| Some((calendar.color()?, calendar)))
+            .collect::<Vec<_>>();
+        calendars.sort_by_cached_key(|(_, calendar)| calendar.uri());
+
+        let mut collisions = Vec::new();
+        for (index, (color, calendar)) in calendars.iter().enumerate() {
+            for (other_color, other_calendar) in &calendars[index + 1..] {
+                if color_distance(color, other_color) < threshold {
+                    collisions.push((calendar.clone(), other_calendar.clone()));
+                }
+            }
+        }
+        collisions
+    }
+
+    /// Find the first slot of the given duration, after the given instant, that no event blocks.
+    ///
+    /// Only the events of the calendars in `within` are considered, or of every calendar if it is
+    /// empty. Transparent events and events hidden from the event lists (see
+    /// [`Manager::is_hidden`]) never block time. With `work_hours`, the slot must fit between
+    /// the given times on a single day, in the time zone of `after`.
+    ///
+    /// Returns `None` if no slot is found within a year.
+    pub fn next_free_slot(
+        &self,
+        after: &Zoned,
+        duration: jiff::Span,
+        within: &[&str],
+        work_hours: Option<(jiff::civil::Time, jiff::civil::Time)>,
+    ) -> Option<Timeframe> {
+        if work_hours.is_some_and(|(open, close)| open >= close) {
+            return None;
+        }
+
+        let tz = after.0.time_zone().clone();
+        let day_start = |date: jiff::civil::Date| date.to_zoned(tz.clone()).ok();
+
+        let mut busy_intervals = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Event(event) => Some(event.clone()),
+                _ => None,
+            })
+            .filter(|event| {
+                event.transparency() == EventTransparency::Opaque
+                    && !self.is_hidden(event)
+                    && (within.is_empty() || within.contains(&event.calendar().uri().as_str()))
+            })
+            .filter_map(|event| {
+                let timeframe = event.timeframe()?;
+                if timeframe.all_day() {
+                    Some((
+                        day_start(timeframe.start().date())?.timestamp(),
+                        day_start(timeframe.end().date())?.timestamp(),
+                    ))
+                } else {
+                    Some((
+                        timeframe.start().0.timestamp(),
+                        timeframe.end().0.timestamp(),
+                    ))
+                }
+            })
+            .filter(|(_, end)| *end > after.0.timestamp())
+            .collect::<Vec<_>>();
+        busy_intervals.sort();
+
+        let limit = after
+            .0
+            .checked_add(jiff::Span::new().days(FREE_SLOT_SEARCH_DAYS))
+            .ok()?;
+        let mut candidate = after.0.clone();
+        while candidate < limit {
+            if let Some((open, close)) = work_hours {
+                let opening = candidate
+                    .date()
+                    .to_datetime(open)
+                    .to_zoned(tz.clone())
+                    .ok()?;
+                let closing = candidate
+                    .date()
+                    .to_datetime(close)
+                    .to_zoned(tz.clone())
+                    .ok()?;
+                if candidate < opening {
+                    candidate = opening;
+                }
+                if candidate.checked_add(duration).ok()? > closing {
+                    let tomorrow = candidate.date().tomorrow().ok()?;
+                    candidate = tomorrow.to_datetime(open).to_zoned(tz.clone()).ok()?;
+                    continue;
+                }
+            }
+
+            let slot_end = candidate.checked_add(duration).ok()?;
+            let blocking_end = busy_intervals
+                .iter()
+                .find(|(start, end)| *start < slot_end.timestamp() && candidate.timestamp() < *end)
+                .map(|(_, end)| *end);
+            match blocking_end {
+                Some(end) => candidate = end.to_zoned(tz.clone()),
+                None => {
+                    return Some(Timeframe::new(false, Zoned(candidate), Zoned(slot_end)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Compute the time spent in events between `start` and `end`, keyed by calendar URI.
+    ///
+    /// Only the part of each event within the range is counted, so overlapping events are each
+    /// counted in full. Transparent events, all-day events and events hidden from the event lists
+    /// (see [`Manager::is_hidden`]) do not count, so calendars the user hid are left out like
+    /// calendars without any counted time.
+    ///
+    /// Durations are expressed in hours and smaller units.
+    pub fn busy_duration_per_calendar(
+        &self,
+        start: jiff::Timestamp,
+        end: jiff::Timestamp,
+    ) -> HashMap<String, jiff::Span> {
+        let mut busy_durations = HashMap::<String, jiff::SignedDuration>::new();
+
+        let events = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Event(event) => Some(event.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for event in events {
+            if event.transparency() != EventTransparency::Opaque || self.is_hidden(&event) {
+                continue;
+            }
+            let Some(timeframe) = event.timeframe() else {
+                continue;
+            };
+            if timeframe.all_day() {
+                continue;
+            }
+
+            let event_start = timeframe.start().0.timestamp().max(start);
+            let event_end = timeframe.end().0.timestamp().min(end);
+            if event_start >= event_end {
+                continue;
+            }
+
+            *busy_durations.entry(event.calendar().uri()).or_default() +=
+                event_start.duration_until(event_end);
+        }
+
+        busy_durations
+            .into_iter()
+            .filter_map(|(calendar_uri, duration)| {
+                let span = jiff::Span::try_from(duration)
+                    .and_then(|span| span.round(jiff::SpanRound::new().largest(jiff::Unit::Hour)))
+                    .inspect_err(|err| warn!("Busy time of {calendar_uri} is out of range: {err}"))
+                    .ok()?;
+                Some((calendar_uri, span))
+            })
+            .collect()
+    }
+
+    /// The earliest start and the latest end of the events of the given calendars, or of every
+    /// calendar.
+    ///
+    /// Returns `None` if there are no such events. All-day events start and end at midnight UTC
+    /// for the comparison.
+    ///
+    /// Times are compared as instants, once parsed, so the bounds are computed from the events
+    /// loaded by this manager rather than by the database, which only compares them as strings.
+    /// Events of collections whose calendars are not loaded yet, see
+    /// [`Collection::ensure_calendars_loaded`], are not taken into account.
+    pub fn event_time_bounds(&self, calendar_uris: Option<&[&str]>) -> Option<(Instant, Instant)> {
+        let timeframes = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Event(event) => Some(event.clone()),
+                _ => None,
+            })
+            .filter(|event| {
+                calendar_uris.is_none_or(|calendar_uris| {
+                    calendar_uris.contains(&event.calendar().uri().as_str())
+                })
+            })
+            .filter_map(|event| event.timeframe())
+            .collect::<Vec<_>>();
+
+        let instant = |all_day: bool, zoned: Zoned| {
+            if all_day {
+                Instant::Date(zoned.date())
+            } else {
+                Instant::Zoned(zoned)
+            }
+        };
+        let timestamp = |instant: &Instant| match instant {
+            Instant::Date(date) => Zoned::from(*date).0.timestamp(),
+            Instant::Zoned(zoned) => zoned.0.timestamp(),
+        };
+
+        let start = timeframes
+            .iter()
+            .map(|timeframe| instant(timeframe.all_day(), timeframe.start()))
+            .min_by_key(timestamp)?;
+        let end = timeframes
+            .iter()
+            .map(|timeframe| instant(timeframe.all_day(), timeframe.end()))
+            .max_by_key(timestamp)?;
+        Some((start, end))
+    }
+
+    /// Retrieve the all-day events covering the given day, sorted by name.
+    ///
+    /// Timed events happening on that day are not included, so they can be displayed separately,
+    /// and neither are events hidden from the event lists (see [`Manager::is_hidden`]).
+    pub fn all_day_events_on(&self, day: jiff::civil::Date) -> ListStore {
+        let mut events: Vec<Event> = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Event(event) => Some(event.clone()),
+                _ => None,
+            })
+            .filter(|event| !self.is_hidden(event))
+            .filter(|event| {
+                event.timeframe().is_some_and(|timeframe| {
+                    timeframe.all_day()
+                        && timeframe.start().date() <= day
+                        && timeframe.days().any(|covered_day| covered_day == day)
+                })
+            })
+            .collect();
+        events.sort_by_cached_key(|event| name_collation_key(&event.name()));
+
+        let all_day_events = ListStore::new::<Event>();
+        all_day_events.extend_from_slice(&events);
+        all_day_events
+    }
+
+    /// Whether the given event is left out of the event lists, because its calendar is not
+    /// [visible](Calendar::visible), or because the user declined it while
+    /// [`Manager::hide_declined`] is set.
+    ///
+    /// The user is identified as described in [`Event::user_email`].
+    pub fn is_hidden(&self, event: &Event) -> bool {
+        !event.calendar().visible()
+            || self.hide_declined()
+                && event.user_email().is_some_and(|email| {
+                    event.participation_of(&email) == Some(ParticipationStatus::Declined)
+                })
+    }
+
+    /// Refresh the event count and the live searches once a calendar was shown or hidden.
+    pub(crate) fn calendar_visibility_changed(&self) {
+        self.imp().update_events_remaining_today();
+        self.imp().schedule_live_search_refresh();
+    }
+
+    /// Retrieve the events starting between now and `within` from now, sorted by start.
+    ///
+    /// Recurring events are expanded, and appear once, at their next occurrence, which may be an
+    /// event overriding it (see [`Event::occurrences_in_range`]). At most `limit` events are
+    /// returned. Events that already started are left out, and so are events hidden from the
+    /// event lists (see [`Manager::is_hidden`]).
+    ///
+    /// The list is a snapshot: it is not updated as events change or time passes, so it should
+    /// be retrieved again to refresh it.
+    pub fn upcoming_events(&self, within: jiff::Span, limit: u32) -> ListStore {
+        let upcoming_events = ListStore::new::<Event>();
+
+        let now = jiff::Zoned::now();
+        let Ok(horizon) = now.checked_add(within) else {
+            return upcoming_events;
+        };
+
+        let candidates = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Event(event) => Some(event.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // All-day occurrences are expanded in UTC, so the range is widened by a day on each side
+        // before their start is compared in the local time zone
+        let one_day = jiff::Span::new().days(1);
+        let (Ok(range_start), Ok(range_end)) =
+            (now.checked_sub(one_day), horizon.checked_add(one_day))
+        else {
+            return upcoming_events;
+        };
+
+        let mut events: Vec<(jiff::Timestamp, Event)> = candidates
+            .into_iter()
+            .filter(|event| !self.is_hidden(event))
+            .filter_map(|event| {
+                event
+                    .occurrences_in_range(
+                        &Zoned(range_start.clone()),
+                        &Zoned(range_end.clone()),
+                        &RecurrenceFilter::default(),
+                    )
+                    .into_iter()
+                    .find_map(|occurrence| {
+                        let start = occurrence.timeframe.start_in(now.time_zone()).0;
+                        (start >= now && start <= horizon)
+                            .then(|| (start.timestamp(), occurrence.event))
+                    })
+            })
+            .collect();
+        events.sort_by_key(|(start, _)| *start);
+
+        for (_, event) in events.into_iter().take(limit as usize) {
+            upcoming_events.append(&event);
+        }
+
+        upcoming_events
+    }
+
+    /// The durations recorded since the manager was created or the metrics were reset, while
+    /// [`Manager::collect_metrics`] was set.
+    pub fn metrics(&self) -> Metrics {
+        self.imp().metrics.get()
+    }
+
+    /// Forget the durations recorded so far.
+    pub fn reset_metrics(&self) {
+        self.imp().metrics.take();
+    }
+
+    /// The URIs of the resources skipped while loading because their parent does not exist in
+    /// the database.
+    ///
+    /// Parents are loaded before their children, so this points to an inconsistent database,
+    /// for instance to show in a diagnostics view. Resources created later on whose parent is
+    /// not found are not included.
+    pub fn orphaned_resources(&self) -> Vec<String> {
+        self.imp().orphaned_resources.borrow().clone()
+    }
+
+    /// The visibility of every calendar, keyed by calendar URI, for the application to persist.
+    ///
+    /// See [`Manager::apply_visibility_state`] to restore it.
+    pub fn visibility_state(&self) -> HashMap<String, bool> {
+        self.imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Calendar(calendar) => Some((calendar.uri(), calendar.visible())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Restore the visibility of calendars saved with [`Manager::visibility_state`].
+    ///
+    /// URIs of calendars that are not loaded are ignored, and calendars missing from `state`
+    /// keep their visibility, which is visible for calendars that appeared since it was saved.
+    pub fn apply_visibility_state(&self, state: &HashMap<String, bool>) {
+        let calendars = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Calendar(calendar) => Some(calendar.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // The pool is unlocked, as handlers of the visibility may query the manager
+        for calendar in calendars {
+            if let Some(&visible) = state.get(&calendar.uri()) {
+                calendar.set_visible(visible);
+            }
+        }
+    }
+
+    /// Retrieve the calendars whose sync token differs from the one in `known_tokens`, keyed by
+    /// calendar URI.
+    ///
+    /// Calendars missing from `known_tokens`, and calendars without a sync token, always need to
+    /// be synced.
+    pub fn calendars_needing_sync(&self, known_tokens: &HashMap<String, String>) -> Vec<Calendar> {
+        self.imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Calendar(calendar) => Some(calendar.clone()),
+                _ => None,
+            })
+            .filter(|calendar| match calendar.sync_token() {
+                Some(sync_token) => known_tokens.get(&calendar.uri()) != Some(&sync_token),
+                None => true,
+            })
+            .collect()
+    }
+
+    /// Retrieve the calendars new events can be written to.
+    ///
+    /// Read-only calendars, such as subscriptions, are left out, and the model is empty if the
+    /// manager itself is read-only. Calendars are sorted by name.
+    ///
+    /// The model is kept up to date as calendars are created, deleted, updated, moved or loaded,
+    /// until it is dropped.
+    pub fn writable_calendars(&self) -> gio::ListModel {
+        let writable_calendars = ListStore::new::<Calendar>();
+
+        if self.read_only() {
+            return writable_calendars.upcast();
+        }
+
+        // GTK is not a dependency, so this filters the pool by hand rather than through a
+        // `gtk::FilterListModel`
+        let watches = Rc::new(SignalWatches::default());
+        let writable_calendars_weak = writable_calendars.downgrade();
+        let refresh = Rc::new(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[strong]
+            watches,
+            move || {
+                let Some(writable_calendars) = writable_calendars_weak.upgrade() else {
+                    watches.clear();
+                    return;
+                };
+
+                let mut calendars: Vec<Calendar> = obj
+                    .imp()
+                    .resource_pool()
+                    .values()
+                    .filter_map(|resource| match resource {
+                        Resource::Calendar(calendar) if !calendar.read_only() => {
+                            Some(calendar.clone())
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                calendars.sort_by_cached_key(|calendar| name_collation_key(&calendar.name()));
+
+                // The pool is unlocked, as handlers of the model may query the manager
+                let current = writable_calendars
+                    .iter::<Calendar>()
+                    .filter_map(Result::ok)
+                    .collect::<Vec<_>>();
+                if current != calendars {
+                    writable_calendars.splice(0, writable_calendars.n_items(), &calendars);
+                }
+            }
+        ));
+
+        // Calendars loaded lazily or moved between collections are not reported as resource
+        // changes, so collections are watched as well. Their signals are emitted with the pool
+        // locked, so the refresh is deferred.
+        let refresh_later = clone!(
+            #[strong]
+            refresh,
+            move || {
+                glib::idle_add_local_once(clone!(
+                    #[strong]
+                    refresh,
+                    move || refresh()
+                ));
+            }
+        );
+        let refresh_later = Rc::new(refresh_later);
+        let watch_collections = clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[strong]
+            refresh_later,
+            #[strong]
+            watches,
+            move || {
+                let collections = obj
+                    .imp()
+                    .resource_pool()
+                    .values()
+                    .filter_map(|resource| match resource {
+                        Resource::Collection(collection) => Some(collection.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                for collection in collections {
+                    watches.watch(&collection, |collection| {
+                        vec![
+                            collection.connect_calendar_added(clone!(
+                                #[strong]
+                                refresh_later,
+                                move |_, _| refresh_later()
+                            )),
+                            collection.connect_calendar_removed(clone!(
+                                #[strong]
+                                refresh_later,
+                                move |_, _| refresh_later()
+                            )),
+                        ]
+                    });
+                }
+            }
+        );
+        let watch_collections = Rc::new(watch_collections);
+
+        watches.watch(self, |obj| {
+            let on_change = clone!(
+                #[strong]
+                refresh,
+                #[strong]
+                watch_collections,
+                move || {
+                    watch_collections();
+                    refresh();
+                }
+            );
+            let on_change = Rc::new(on_change);
+
+            vec![
+                obj.connect_resource_changed(clone!(
+                    #[strong]
+                    on_change,
+                    move |_, _, _| on_change()
+                )),
+                obj.connect_structure_ready(clone!(
+                    #[strong]
+                    on_change,
+                    move |_| on_change()
+                )),
+                obj.connect_loading_finished(move |_| on_change()),
+            ]
+        });
+
+        watch_collections();
+        refresh();
+        writable_calendars.upcast()
+    }
+
+    /// Export every calendar, across all providers, to the iCalendar format.
+    ///
+    /// Each calendar is written to the stream as its own `VCALENDAR`, as soon as it is
+    /// serialized, so large databases are not held in memory at once.
+    pub fn export_all_ical(&self, stream: &impl IsA<gio::OutputStream>) -> Result<(), glib::Error> {
+        let mut calendars: Vec<Calendar> = self
+            .imp()
+            .resource_pool()
+            .values()
+            .filter_map(|resource| match resource {
+                Resource::Calendar(calendar) => Some(calendar.clone()),
+                _ => None,
+            })
+            .collect();
+        calendars.sort_by_cached_key(|calendar| name_collation_key(&calendar.name()));
+
+        for calendar in calendars {
+            ical::write_calendar(stream, &calendar)?;
+        }
+
+        stream.flush(None::<&gio::Cancellable>)
+    }
+
+    /// Run a custom read-only SPARQL query against the database.
+    ///
+    /// Only `SELECT` and `ASK` queries are accepted, optionally preceded by `PREFIX` and `BASE`
+    /// declarations and comments. Other queries fail with [`gio::IOErrorEnum::InvalidArgument`].
+    ///
+    /// The results are read straight from the database, and are not tied to the resources held
+    /// by this manager.
+    pub fn read_query(&self, sparql: &str) -> Result<tsparql::SparqlCursor, glib::Error> {
+        if !is_read_only_query(sparql) {
+            return Err(glib::Error::new(
+                gio::IOErrorEnum::InvalidArgument,
+                "Only SELECT and ASK queries are allowed",
+            ));
+        }
+
+        self.imp()
+            .read_connection()
+            .query(sparql, Some(&self.imp().cancellable()))
+    }
+
+    /// Set how failed writes are retried.
+    ///
+    /// By default, writes are not retried. Only the asynchronous writes, whose names end with
+    /// `_future` such as [`Calendar::create_event_future`], are retried, so that waiting between
+    /// retries never blocks the main thread.
+    pub fn set_write_retry(&self, policy: RetryPolicy) {
+        self.imp().assert_owner_thread();
+        self.imp().write_retry.set(policy);
+    }
+
+    /// Watch a single resource for changes.
+    ///
+    /// The returned watcher emits `changed` when the resource at `uri` is created or updated, and
+    /// `deleted` when it is deleted. It stops being notified once it is dropped.
+    pub fn watch(&self, uri: &str) -> ResourceWatcher {
+        let watcher = ResourceWatcher::new(uri);
+        self.imp()
+            .watchers
+            .borrow_mut()
+            .entry(uri.to_string())
+            .or_default()
+            .push(watcher.downgrade());
+        watcher
+    }
+
+    /// The optional parts of the ontology supported by the database.
+    ///
+    /// The ontology is probed once, when the manager is constructed.
+    pub fn supported_features(&self) -> FeatureSet {
+        *self.imp().supported_features()
+    }
+
+    /// Find the event with the given UID, as used by iCalendar and sync protocols.
+    ///
+    /// Importers should look up the UID of each event they read with this before creating it,
+    /// to skip or update the events imported before rather than duplicating them.
+    ///
+    /// Returns `None` if the database does not support UIDs, or if the event is not held by this
+    /// manager.
+    pub fn find_event_by_uid(&self, uid: &str) -> Option<Event> {
+        if !self.supported_features().uid {
+            return None;
+        }
+
+        let statement = self
+            .imp()
+            .read_connection()
+            .query_statement(
+                "SELECT ?uri
+                WHERE {
+                    ?uri a ccm:Event ;
+                        ccm:uid ~uid .
+                }
+                LIMIT 1",
+                Some(&self.imp().cancellable()),
+            )
+            .expect("SPARQL should be valid")
+            .expect("SPARQL should be valid");
+        statement.bind_string("uid", uid);
+
+        let cancellable = self.imp().cancellable();
+        let cursor = match statement.execute(Some(&cancellable)) {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                warn!("Failed to find event with UID {uid}: {err:?}");
+                return None;
+            }
+        };
+
+        match cursor.next(Some(&cancellable)) {
+            Ok(true) => {
+                let uri = cursor.string(0).expect("Query should return a URI");
+                match self.find_resource(&uri) {
+                    Some(Resource::Event(event)) => Some(event),
+                    _ => None,
+                }
+            }
+            Ok(false) => None,
+            Err(err) => {
+                warn!("Failed to find event with UID {uid}: {err:?}");
+                None
+            }
+        }
+    }
+
+    /// List the distinct categories used by events, sorted alphabetically.
+    ///
+    /// Returns an empty list if the database does not support categories.
+    pub fn all_categories(&self) -> Vec<String> {
+        if !self.supported_features().category {
+            return Vec::new();
+        }
+
+        let cancellable = self.imp().cancellable();
+        let cursor = match self.imp().read_connection().query(
+            "SELECT DISTINCT ?category
+            WHERE {
+                ?event a ccm:Event ;
+                    ccm:category ?category .
+            }",
+            Some(&cancellable),
+        ) {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                warn!("Failed to retrieve categories: {err:?}");
+                return Vec::new();
+            }
+        };
+
+        let mut categories = Vec::new();
+        while let Ok(true) = cursor.next(Some(&cancellable)) {
+            let category = cursor.string(0).expect("Query should return a category");
+            categories.push(category.to_string());
+        }
+
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+
+    /// Drop every resource held by this manager, then load them again from the database.
+    ///
+    /// Collections, calendars and events are deleted from their models, emitting their
+    /// `deleted` signals, before being retrieved again. The undo history is cleared.
+    pub fn reset(&self) {
+        self.imp().reset();
+    }
+
+    /// Iterate the main context of this manager until the retrievals and refreshes it spawned
+    /// have completed and no event source is ready.
+    ///
+    /// This is intended for tests, so that assertions run against a settled state. It must not be
+    /// used from UI code, as it blocks until the work is done. Changes made by other processes
+    /// are only seen if their notifications arrived before the context went idle.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn run_until_idle(&self) {
+        let main_context = self
+            .main_context()
+            .expect("main context should be initialized");
+        while self.imp().pending_work.get() > 0 || main_context.pending() {
+            main_context.iteration(true);
+        }
+    }
+
+    /// Buffer the changes reported by the database until [`Manager::end_batch`] is called.
+    ///
+    /// This is meant for bulk writes such as imports: instead of updating the models after each
+    /// write, the changes are applied in a single pass at the end, each resource being read once.
+    /// Batches can be nested, and a batch lasting more than a minute is ended automatically.
+    pub fn begin_batch(&self) {
+        self.imp().begin_batch();
+    }
+
+    /// End a batch begun with [`Manager::begin_batch`], applying the buffered changes once the
+    /// outermost batch ends.
+    pub fn end_batch(&self) {
+        self.imp().end_batch();
+    }
+
+    /// Cancel every query currently in flight.
+    ///
+    /// Cancelled searches return an empty model, and cancelled retrievals stop early. Queries
+    /// started afterwards are not affected.
+    pub fn cancel_pending_queries(&self) {
+        let cancellable = self.imp().cancellable.replace(gio::Cancellable::new());
+        cancellable.cancel();
+    }
+
+    /// Search events matching the given query, keeping the results up to date.
+    ///
+    /// The search runs again shortly after resources change, and the results are replaced in
+    /// place. The search stops being refreshed once the returned model is dropped.
+    pub fn live_search(&self, query: &str, options: &SearchOptions) -> ListStore {
+        let results = self.search_events_with_options(query, options);
+        self.imp().live_searches.borrow_mut().push((
+            query.to_string(),
+            *options,
+            results.downgrade(),
+        ));
+        results
+    }
+
+    /// Search events matching the given query, with the default [`SearchOptions`].
+    pub fn search_events(&self, query: &str) -> ListStore {
+        self.search_events_with_options(query, &SearchOptions::default())
+    }
+
+    /// Search events matching the given query.
+    ///
+    /// A query made only of whitespace matches no event.
+    pub fn search_events_with_options(&self, query: &str, options: &SearchOptions) -> ListStore {
+        let search_results = ListStore::new::<Event>();
+        for (event, _) in self.run_search(query, options, None) {
+            search_results.append(&event);
+        }
+        search_results
+    }
+
+    /// Search events matching the given query, along with an excerpt of where they matched.
+    ///
+    /// In the excerpts, the words matching the query are surrounded by `start_marker` and
+    /// `end_marker`, for instance `<b>` and `</b>`. Events whose match cannot be excerpted have
+    /// their name as excerpt.
+    pub fn search_events_with_snippets(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        start_marker: &str,
+        end_marker: &str,
+    ) -> ListStore {
+        let search_results = ListStore::new::<SearchResult>();
+        for (event, snippet) in self.run_search(query, options, Some((start_marker, end_marker))) {
+            let snippet = snippet
+                .filter(|snippet| !snippet.is_empty())
+                .map(String::from)
+                .unwrap_or_else(|| event.name());
+            search_results.append(&SearchResult::new(&event, &snippet));
+        }
+        search_results
+    }
+
+    /// Load an event matched by a search that is not in the pool yet, for instance because its
+    /// creation was not handled yet.
+    ///
+    /// Returns `None` if the event cannot be read, or if its calendar is not loaded.
+    fn load_missing_event(&self, uri: &str) -> Option<Event> {
+        let pre_event = PreEvent::from_uri(
+            self.imp().read_connection(),
+            self.imp().supported_features(),
+            uri,
+            &self.imp().cancellable(),
+        )
+        .inspect_err(|()| warn!("Event \"{uri}\" matched a search but could not be retrieved"))
+        .ok()?;
+
+        let mut resource_pool = self.imp().resource_pool();
+        // The event may have been loaded while it was read
+        if let Some(Resource::Event(event)) = resource_pool.get(uri) {
+            return Some(event.clone());
+        }
+        let Some(Resource::Calendar(calendar)) =
+            resource_pool.get(&pre_event.calendar_uri).cloned()
+        else {
+            debug!(
+                "Event \"{uri}\" matched a search but its calendar {} is not loaded",
+                pre_event.calendar_uri
+            );
+            return None;
+        };
+
+        let event = self.imp().new_event(&calendar, &pre_event);
+        calendar.add_event(&event);
+        resource_pool.insert(uri.to_string(), Resource::Event(event.clone()));
+
+        info!("Event loaded by a search: uri: \"{uri}\"");
+        Some(event)
+    }
+
+    /// Run a search of events, with snippets if markers are given.
+    ///
+    /// Returns no event if the search fails or is cancelled.
+    fn run_search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        snippet_markers: Option<(&str, &str)>,
+    ) -> Vec<(Event, Option<glib::GString>)> {
+        let Some(query) = options.normalize_query(query) else {
+            return Vec::new();
+        };
+
+        let snippet_projection = if snippet_markers.is_some() {
+            "(fts:snippet(?uri, ~start_marker, ~end_marker, \"…\", 16) AS ?snippet)"
+        } else {
+            ""
+        };
+        let statement = self
+            .imp()
+            .read_connection()
+            .query_statement(
+                &format!(
+                    "SELECT ?uri {snippet_projection}
+                    WHERE {{
+                        ?uri a ccm:Event ;
+                            fts:match ~query .
+                    }}"
+                ),
+                Some(&self.imp().cancellable()),
+            )
+            .expect("SPARQL should be valid")
+            .expect("SPARQL should be valid");
+        statement.bind_string("query", &query);
+        if let Some((start_marker, end_marker)) = snippet_markers {
+            statement.bind_string("start_marker", start_marker);
+            statement.bind_string("end_marker", end_marker);
+        }
+
+        let cancellable = self.imp().cancellable();
+        let cursor = match statement.execute(Some(&cancellable)) {
+            Ok(cursor) => cursor,
+            Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => {
+                debug!("Search of events was cancelled");
+                return Vec::new();
+            }
+            Err(err) => {
+                warn!("Failed to search events: {err:?}");
+                return Vec::new();
+            }
+        };
+
+        let mut search_results = Vec::new();
+        let mut fallback_loads = 0;
+
+        loop {
+            match cursor.next(Some(&cancellable)) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => {
+                    debug!("Search of events was cancelled");
+                    cursor.close();
+                    return Vec::new();
+                }
+                Err(err) => {
+                    warn!("Failed to search events: {err:?}");
+                    break;
+                }
+            }
+
+            let uri = cursor.string(0).expect("Query should return a URI");
+
+            let resource = self.imp().resource_pool().get(uri.as_str()).cloned();
+            let event = match resource {
+                Some(Resource::Event(event)) => event,
+                Some(_) => {
+                    warn!("Resource \"{uri}\" matched as an event but is not one");
+                    continue;
+                }
+                None if fallback_loads >= MAX_SEARCH_FALLBACK_LOADS => {
+                    warn!("Event \"{uri}\" is not in resource pool, too many to load");
+                    continue;
+                }
+                None => {
+                    fallback_loads += 1;
+                    let Some(event) = self.load_missing_event(&uri) else {
+                        continue;
+                    };
+                    event
+                }
+            };
+            if self.is_hidden(&event) {
+                continue;
+            }
+
+            let snippet = snippet_markers.and_then(|_| cursor.string(1));
+            search_results.push((event, snippet));
+        }
+
+        search_results
+    }
+}
+
+impl Default for Manager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The Euclidean distance between two colors in sRGB, ignoring their alpha.
+fn color_distance(a: &RGBA, b: &RGBA) -> f32 {
+    let red = a.red() - b.red();
+    let green = a.green() - b.green();
+    let blue = a.blue() - b.blue();
+    (red * red + green * green + blue * blue).sqrt()
+}
+
+/// Convert a color from HSV, with a hue in degrees, to RGB.
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> (f32, f32, f32) {
+    let chroma = value * saturation;
+    let x = chroma * (1. - ((hue / 60.) % 2. - 1.).abs());
+    let m = value - chroma;
+    let (red, green, blue) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+    (red + m, green + m, blue + m)
+}
+
+/// Whether the SPARQL query is a `SELECT` or an `ASK` query, once its prologue is skipped.
+///
+/// Queries with a malformed prologue are not considered read-only.
+fn is_read_only_query(sparql: &str) -> bool {
+    let Some(query) = skip_prologue(sparql) else {
+        return false;
+    };
+    let keyword_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let keyword = &query[..keyword_len];
+    keyword.eq_ignore_ascii_case("SELECT") || keyword.eq_ignore_ascii_case("ASK")
+}
+
+/// Skip the `BASE` and `PREFIX` declarations at the start of a SPARQL query, along with
+/// whitespace and comments, returning the rest of the query.
+///
+/// Declarations do not need whitespace before their IRI, as in `PREFIX ex:<http://example.org/>`.
+fn skip_prologue(sparql: &str) -> Option<&str> {
+    let mut rest = sparql;
+    loop {
+        rest = skip_whitespace_and_comments(rest);
+        if let Some(after) = strip_keyword(rest, "BASE") {
+            rest = skip_iri(skip_whitespace_and_comments(after))?;
+        } else if let Some(after) = strip_keyword(rest, "PREFIX") {
+            let after = skip_whitespace_and_comments(after);
+            let name_len = after.find(':')?;
+            if after[..name_len].contains(|c: char| c.is_whitespace() || c == '<') {
+                return None;
+            }
+            rest = skip_iri(skip_whitespace_and_comments(&after[name_len + 1..]))?;
+        } else {
+            return Some(rest);
+        }
+    }
+}
+
+/// Skip the whitespace and the comments, running from `#` to the end of the line, at the start
+/// of the given SPARQL.
+fn skip_whitespace_and_comments(mut sparql: &str) -> &str {
+    loop {
+        sparql = sparql.trim_start();
+        match sparql.strip_prefix('#') {
+            Some(comment) => sparql = comment.find('\n').map_or("", |end| &comment[end..]),
+            None => return sparql,
+        }
+    }
+}
+
+/// Remove the given keyword, in any case, from the start of the given SPARQL.
+fn strip_keyword<'a>(sparql: &'a str, keyword: &str) -> Option<&'a str> {
+    let prefix = sparql.get(..keyword.len())?;
+    let rest = &sparql[keyword.len()..];
+    (prefix.eq_ignore_ascii_case(keyword) && !rest.starts_with(|c: char| c.is_ascii_alphanumeric()))
+        .then_some(rest)
+}
+
+/// Skip the IRI, such as `<http://example.org/>`, at the start of the given SPARQL.
+fn skip_iri(sparql: &str) -> Option<&str> {
+    let iri = sparql.strip_prefix('<')?;
+    let end = iri.find('>')?;
+    Some(&iri[end + 1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_queries_are_accepted() {
+        for sparql in [
+            "SELECT ?s WHERE { ?s a ccm:Event }",
+            "ask { ?s a ccm:Calendar }",
+            "SELECT* { ?s ?p ?o }",
+            "PREFIX ex: <http://example.org/> SELECT ?s { ?s a ex:Thing }",
+            "PREFIX ex:<http://example.org/>SELECT ?s { ?s a ex:Thing }",
+            "prefix : <http://example.org/#> base <http://example.org/> ASK { ?s a :Thing }",
+            "# Every event\nSELECT ?s { ?s a ccm:Event }",
+            "  # Prefixes\n  PREFIX ex: <http://example.org/> # trailing\n\tSELECT ?s {}",
+        ] {
+            assert!(is_read_only_query(sparql), "{sparql}");
+        }
+    }
+
+    #[test]
+    fn other_queries_are_rejected() {
+        for sparql in [
+            "",
+            "# SELECT ?s {}",
+            "INSERT DATA { <urn:a> a ccm:Event }",
+            "# SELECT\nDELETE WHERE { ?s ?p ?o }",
+            "PREFIX ex:<http://example.org/> INSERT DATA { <urn:a> a ex:Thing }",
+            "PREFIX ex: SELECT ?s {}",
+            "PREFIX ex <http://example.org/> SELECT ?s {}",
+            "BASE <http://example.org/ SELECT ?s {}",
+            "SELECTION",
+        ] {
+            assert!(!is_read_only_query(sparql), "{sparql}");
+        }
     }
 }
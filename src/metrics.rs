@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/// The number and total duration of runs of an operation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OperationMetrics {
+    pub count: u32,
+    pub total_duration: Duration,
+}
+
+impl OperationMetrics {
+    /// The mean duration of a run, or `None` if the operation never ran.
+    pub fn average_duration(&self) -> Option<Duration> {
+        self.total_duration.checked_div(self.count)
+    }
+
+    pub(crate) fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total_duration += duration;
+    }
+}
+
+/// Timings of the work of a manager, recorded while
+/// [`Manager::collect_metrics`](crate::Manager::collect_metrics) is set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    /// Queries loading the providers.
+    pub provider_retrievals: OperationMetrics,
+    /// Queries loading the collections.
+    pub collection_retrievals: OperationMetrics,
+    /// Queries loading the calendars, of every collection or of a single one.
+    pub calendar_retrievals: OperationMetrics,
+    /// Queries loading the events, of every calendar or of the calendars of a single collection.
+    pub event_retrievals: OperationMetrics,
+    /// Handling of the changes reported by the notifier, including the queries re-reading the
+    /// changed resources.
+    pub notifier_batches: OperationMetrics,
+}
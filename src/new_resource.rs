@@ -0,0 +1,20 @@
+use gdk::RGBA;
+
+use crate::Timeframe;
+
+/// The properties of a calendar to create.
+#[derive(Debug, Clone)]
+pub struct NewCalendar {
+    pub name: String,
+    pub color: RGBA,
+}
+
+/// The properties of an event to create.
+#[derive(Debug, Clone)]
+pub struct NewEvent {
+    pub name: String,
+    pub description: String,
+    pub timeframe: Timeframe,
+    /// The location of the event, left empty if there is none.
+    pub location: String,
+}
@@ -1,16 +1,16 @@
 use gdk::gio;
 use tracing::error;
-use tsparql::SparqlConnection;
+use tsparql::{SparqlConnection, prelude::*};
+
+use crate::FeatureSet;
 
 mod pre_calendar;
 mod pre_collection;
 mod pre_event;
 mod pre_provider;
 
-use self::{
-    pre_calendar::PreCalendar, pre_collection::PreCollection, pre_event::PreEvent,
-    pre_provider::PreProvider,
-};
+use self::pre_collection::PreCollection;
+pub use self::{pre_calendar::PreCalendar, pre_event::PreEvent, pre_provider::PreProvider};
 
 pub enum PreResource {
     Provider(PreProvider),
@@ -20,159 +20,78 @@ pub enum PreResource {
 }
 
 impl PreResource {
-    pub fn from_uri(read_connection: &SparqlConnection, uri: &str) -> Result<Self, ()> {
-        let is_event = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Event .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
-                Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
-                }
-            };
-
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
+    pub fn from_uri(
+        read_connection: &SparqlConnection,
+        features: &FeatureSet,
+        uri: &str,
+        cancellable: &gio::Cancellable,
+    ) -> Result<Self, ()> {
+        let statement = read_connection
+            .query_statement(
+                "SELECT ?type
+                WHERE {
+                    ~uri a ?type .
+                    FILTER (?type IN (ccm:Provider, ccm:Collection, ccm:Calendar, ccm:Event))
                 }
+                LIMIT 1",
+                Some(cancellable),
+            )
+            .unwrap()
+            .unwrap();
+        statement.bind_string("uri", uri);
+
+        let cursor = match statement.execute(Some(cancellable)) {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                error!("Failed to execute query: {err}");
+                return Err(());
             }
         };
 
-        if is_event {
-            return Ok(Self::Event(PreEvent::from_uri(read_connection, uri)?));
-        }
-
-        let is_calendar = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Calendar .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
-                Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
-                }
-            };
-
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
-                }
+        let resource_type = match cursor.next(Some(cancellable)) {
+            Ok(true) => cursor
+                .string(0)
+                .expect("Query should return a type")
+                .to_string(),
+            Ok(false) => {
+                error!("resource has no type");
+                return Err(());
             }
-        };
-
-        if is_calendar {
-            return Ok(Self::Calendar(PreCalendar::from_uri(read_connection, uri)?));
-        }
-
-        let is_collection = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Collection .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
-                Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
-                }
-            };
-
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
-                }
+            Err(err) => {
+                error!("Failed to fetch resource type: {err}");
+                return Err(());
             }
         };
+        cursor.close();
 
-        if is_collection {
-            return Ok(Self::Collection(PreCollection::from_uri(
+        match resource_type.rsplit(['#', '/', ':']).next() {
+            Some("Event") => Ok(Self::Event(PreEvent::from_uri(
                 read_connection,
+                features,
                 uri,
-            )?));
-        }
-
-        let is_provider = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Provider .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
-                Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
-                }
-            };
-
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
-                }
+                cancellable,
+            )?)),
+            Some("Calendar") => Ok(Self::Calendar(PreCalendar::from_uri(
+                read_connection,
+                features,
+                uri,
+                cancellable,
+            )?)),
+            Some("Collection") => Ok(Self::Collection(PreCollection::from_uri(
+                read_connection,
+                uri,
+                cancellable,
+            )?)),
+            Some("Provider") => Ok(Self::Provider(PreProvider::from_uri(
+                read_connection,
+                features,
+                uri,
+                cancellable,
+            )?)),
+            _ => {
+                error!("Resource is of unknown type {resource_type}");
+                Err(())
             }
-        };
-
-        if is_provider {
-            return Ok(Self::Provider(PreProvider::from_uri(read_connection, uri)?));
         }
-
-        error!("Resource is of unknown type");
-        Err(())
     }
 }
@@ -2,11 +2,78 @@ use gdk::{RGBA, gio};
 use tracing::error;
 use tsparql::{SparqlConnection, prelude::*};
 
+use crate::{FeatureSet, Zoned};
+
 pub struct PreCalendar {
     pub uri: String,
     pub collection_uri: String,
     pub name: String,
     pub color: RGBA,
+    pub trashed: bool,
+    /// The URL of the remote feed, if the calendar is a subscription.
+    pub subscription_url: Option<String>,
+    pub last_refreshed: Option<Zoned>,
+    pub sync_token: Option<String>,
+    /// The position of the calendar set by the user, or 0 if it has none.
+    pub sort_order: i32,
+    /// The URL the calendar is synced from, if any.
+    pub source_url: Option<String>,
+    /// The description of the calendar, empty if it has none.
+    pub description: String,
+}
+
+impl PreCalendar {
+    /// The SPARQL pattern binding `?subscription_url` and `?last_refreshed` for the calendar
+    /// bound to the given variable or parameter.
+    pub fn subscription_pattern(features: &FeatureSet, variable: &str) -> String {
+        if !features.subscription {
+            return String::new();
+        }
+
+        format!(
+            "OPTIONAL {{
+                {variable} ccm:subscriptionUrl ?subscription_url .
+                OPTIONAL {{ {variable} ccm:lastRefreshed ?last_refreshed }}
+            }}"
+        )
+    }
+
+    /// The SPARQL pattern binding `?sync_token` for the calendar bound to the given variable or
+    /// parameter.
+    pub fn sync_token_pattern(features: &FeatureSet, variable: &str) -> String {
+        if !features.sync_token {
+            return String::new();
+        }
+
+        format!("OPTIONAL {{ {variable} ccm:syncToken ?sync_token }}")
+    }
+
+    /// The SPARQL pattern binding `?sort_order` for the calendar bound to the given variable or
+    /// parameter.
+    pub fn sort_order_pattern(features: &FeatureSet, variable: &str) -> String {
+        if !features.sort_order {
+            return String::new();
+        }
+
+        format!("OPTIONAL {{ {variable} ccm:sortOrder ?sort_order }}")
+    }
+
+    /// The SPARQL pattern binding `?source_url` and `?description` for the calendar bound to the
+    /// given variable or parameter.
+    pub fn details_pattern(features: &FeatureSet, variable: &str) -> String {
+        let mut pattern = String::new();
+        if features.source {
+            pattern.push_str(&format!(
+                "OPTIONAL {{ {variable} ccm:source ?source_url }}\n"
+            ));
+        }
+        if features.calendar_description {
+            pattern.push_str(&format!(
+                "OPTIONAL {{ {variable} ccm:calendarDescription ?description }}\n"
+            ));
+        }
+        pattern
+    }
 }
 
 impl PreCalendar {
@@ -15,23 +82,47 @@ impl PreCalendar {
     /// # Panics
     ///
     /// This function may panic if the given URI is invalid or does not point to a calendar resource.
-    pub fn from_uri(read_connection: &SparqlConnection, uri: &str) -> Result<Self, ()> {
+    pub fn from_uri(
+        read_connection: &SparqlConnection,
+        features: &FeatureSet,
+        uri: &str,
+        cancellable: &gio::Cancellable,
+    ) -> Result<Self, ()> {
+        let trashed_pattern = if features.trash {
+            "OPTIONAL { ~uri ccm:trashed ?trashed }"
+        } else {
+            ""
+        };
+
+        let subscription_pattern = Self::subscription_pattern(features, "~uri");
+        let sync_token_pattern = Self::sync_token_pattern(features, "~uri");
+        let sort_order_pattern = Self::sort_order_pattern(features, "~uri");
+        let details_pattern = Self::details_pattern(features, "~uri");
+
         let statement = read_connection
             .query_statement(
-                "SELECT ?name ?color ?collection
-                WHERE {
-                    ~uri a ccm:Calendar ;
-                        ccm:collection ?collection ;
-                        ccm:calendarName ?name ;
-                        ccm:color ?color .
-                }",
-                None::<&gio::Cancellable>,
+                &format!(
+                    "SELECT ?name ?color ?collection ?trashed ?subscription_url ?last_refreshed
+                        ?sync_token ?sort_order ?source_url ?description
+                    WHERE {{
+                        ~uri a ccm:Calendar ;
+                            ccm:collection ?collection ;
+                            ccm:calendarName ?name ;
+                            ccm:color ?color .
+                        {trashed_pattern}
+                        {subscription_pattern}
+                        {sync_token_pattern}
+                        {sort_order_pattern}
+                        {details_pattern}
+                    }}"
+                ),
+                Some(cancellable),
             )
             .expect("SPARQL should be valid")
             .expect("SPARQL should be valid");
         statement.bind_string("uri", uri);
 
-        let cursor = match statement.execute(None::<&gio::Cancellable>) {
+        let cursor = match statement.execute(Some(cancellable)) {
             Ok(cursor) => cursor,
             Err(err) => {
                 error!("Failed to create calendar: {err:?}");
@@ -39,7 +130,7 @@ impl PreCalendar {
             }
         };
 
-        match cursor.next(None::<&gio::Cancellable>) {
+        match cursor.next(Some(cancellable)) {
             Ok(true) => {
                 let calendar_name = cursor
                     .string(0)
@@ -58,11 +149,25 @@ impl PreCalendar {
                 let collection_uri = cursor
                     .string(2)
                     .expect("Query should return a collection URI");
+                let trashed = cursor.is_boolean(3);
+                let subscription_url = cursor.string(4).map(|url| url.to_string());
+                let last_refreshed = cursor.string(5).and_then(|date| date.parse().ok());
+                let sync_token = cursor.string(6).map(|token| token.to_string());
+                let sort_order = cursor.integer(7) as i32;
+                let source_url = cursor.string(8).map(|url| url.to_string());
+                let description = cursor.string(9).unwrap_or_default().to_string();
                 let calendar = Self {
                     uri: uri.to_string(),
                     collection_uri: collection_uri.to_string(),
                     name: calendar_name.to_string(),
                     color: calendar_color,
+                    trashed,
+                    subscription_url,
+                    last_refreshed,
+                    sync_token,
+                    sort_order,
+                    source_url,
+                    description,
                 };
 
                 Ok(calendar)
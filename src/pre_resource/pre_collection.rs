@@ -1,4 +1,6 @@
-use tsparql::SparqlConnection;
+use gdk::gio;
+use tracing::error;
+use tsparql::{SparqlConnection, prelude::*};
 
 pub struct PreCollection {
     pub uri: String,
@@ -12,7 +14,59 @@ impl PreCollection {
     /// # Panics
     ///
     /// This function may panic if the given URI is invalid or does not point to a collection resource.
-    pub fn from_uri(_read_connection: &SparqlConnection, _uri: &str) -> Result<Self, ()> {
-        todo!()
+    pub fn from_uri(
+        read_connection: &SparqlConnection,
+        uri: &str,
+        cancellable: &gio::Cancellable,
+    ) -> Result<Self, ()> {
+        let statement = read_connection
+            .query_statement(
+                "SELECT ?provider_uri ?name
+                WHERE {
+                    ~uri a ccm:Collection ;
+                        ccm:provider ?provider_uri ;
+                        ccm:collectionName ?name .
+                }",
+                Some(cancellable),
+            )
+            .expect("SPARQL should be valid")
+            .expect("SPARQL should be valid");
+        statement.bind_string("uri", uri);
+
+        let cursor = match statement.execute(Some(cancellable)) {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                error!("Failed to create collection: {err:?}");
+                return Err(());
+            }
+        };
+
+        match cursor.next(Some(cancellable)) {
+            Ok(true) => {
+                let provider_uri = cursor
+                    .string(0)
+                    .expect("Query should return a provider URI")
+                    .to_string();
+                let name = cursor
+                    .string(1)
+                    .expect("Query should return a collection name")
+                    .to_string();
+                let collection = Self {
+                    uri: uri.to_string(),
+                    provider_uri,
+                    name,
+                };
+
+                Ok(collection)
+            }
+            Ok(false) => {
+                error!("Resource {uri} was created but is not found in database");
+                Err(())
+            }
+            Err(e) => {
+                error!("Encountered glib error: {}", e);
+                Err(())
+            }
+        }
     }
 }
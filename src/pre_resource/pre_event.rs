@@ -1,7 +1,13 @@
-use gdk::gio;
-use tracing::error;
+use gdk::{RGBA, gio};
+use tracing::{error, warn};
 use tsparql::{SparqlConnection, prelude::*};
 
+use crate::{
+    Attendee, Event, EventTransparency, FeatureSet, MalformedTimePolicy, ParticipationStatus,
+    RecurrenceRule, Timeframe, Zoned,
+};
+
+#[derive(Debug, Clone)]
 pub struct PreEvent {
     pub uri: String,
     pub calendar_uri: String,
@@ -10,34 +16,359 @@ pub struct PreEvent {
     pub all_day: bool,
     pub start: String,
     pub end: String,
+    pub color: Option<RGBA>,
+    pub transparency: EventTransparency,
+    /// The occurrences excluded from the recurrence, sorted.
+    pub exdates: Vec<Zoned>,
+    pub attendees: Vec<Attendee>,
+    pub recurrence: Option<RecurrenceRule>,
+    /// The URI of the recurring event this event overrides an occurrence of.
+    pub recurrence_of: Option<String>,
+    /// The start of the occurrence this event overrides, as generated by the recurrence.
+    pub recurrence_id: Option<Zoned>,
 }
 
+/// Events are compared by content. Times are compared once parsed, so that the same time
+/// written differently in the database, for instance with another offset notation, is equal.
+impl PartialEq for PreEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.uri == other.uri
+            && self.calendar_uri == other.calendar_uri
+            && self.name == other.name
+            && self.description == other.description
+            && self.has_same_times(other)
+            && self.color == other.color
+            && self.transparency == other.transparency
+            && self.exdates == other.exdates
+            && self.attendees == other.attendees
+            && self.recurrence == other.recurrence
+            && self.recurrence_of == other.recurrence_of
+            && self.recurrence_id == other.recurrence_id
+    }
+}
+
+impl Eq for PreEvent {}
+
 impl PreEvent {
+    /// Whether the time frames of both events are the same, including their time zones.
+    ///
+    /// Time frames that cannot be parsed are compared as written.
+    fn has_same_times(&self, other: &Self) -> bool {
+        let (Ok(timeframe), Ok(other_timeframe)) =
+            (self.parse_timeframe(), other.parse_timeframe())
+        else {
+            return self.all_day == other.all_day
+                && self.start == other.start
+                && self.end == other.end;
+        };
+
+        let same_time = |a: Zoned, b: Zoned| {
+            a.0.timestamp() == b.0.timestamp()
+                && a.0.time_zone().iana_name() == b.0.time_zone().iana_name()
+        };
+        if timeframe.all_day() != other_timeframe.all_day() {
+            false
+        } else if timeframe.all_day() {
+            timeframe.start().date() == other_timeframe.start().date()
+                && timeframe.end().date() == other_timeframe.end().date()
+        } else {
+            same_time(timeframe.start(), other_timeframe.start())
+                && same_time(timeframe.end(), other_timeframe.end())
+        }
+    }
+}
+
+impl PreEvent {
+    /// Take a snapshot of an existing event, to compare it against a freshly retrieved one.
+    pub fn from_event(event: &Event) -> Self {
+        let timeframe = event.timeframe().unwrap_or_default();
+        let (start, end) = timeframe.to_db_strings();
+
+        Self {
+            uri: event.uri(),
+            calendar_uri: event.calendar().uri(),
+            name: event.name(),
+            description: event.description(),
+            all_day: timeframe.all_day(),
+            start,
+            end,
+            color: event.color(),
+            transparency: event.transparency(),
+            exdates: event.exdates(),
+            attendees: event.attendees(),
+            recurrence: event.recurrence(),
+            recurrence_of: event.recurrence_of(),
+            recurrence_id: event.recurrence_id(),
+        }
+    }
+
+    /// The SPARQL pattern binding `?recurrence` to the recurrence rule of the event bound to the
+    /// given variable or parameter, and `?recurrence_of` and `?recurrence_id` to the occurrence
+    /// it overrides.
+    pub fn recurrence_pattern(features: &FeatureSet, variable: &str) -> String {
+        let mut pattern = String::new();
+        if features.recurrence {
+            pattern.push_str(&format!(
+                "OPTIONAL {{ {variable} ccm:eventRecurrence ?recurrence }}\n"
+            ));
+        }
+        if features.overrides {
+            pattern.push_str(&format!(
+                "OPTIONAL {{
+                    {variable} ccm:recurrenceOf ?recurrence_of ;
+                        ccm:recurrenceId ?recurrence_id .
+                }}"
+            ));
+        }
+        pattern
+    }
+
+    /// Parse the recurrence rule bound by [`PreEvent::recurrence_pattern`].
+    ///
+    /// Invalid and unsupported rules are ignored, leaving the event with a single occurrence.
+    pub fn parse_recurrence(uri: &str, recurrence: Option<&str>) -> Option<RecurrenceRule> {
+        let recurrence = recurrence.filter(|recurrence| !recurrence.is_empty())?;
+        recurrence
+            .parse()
+            .inspect_err(|err| warn!("Event \"{uri}\" has an unusable recurrence rule: {err}"))
+            .ok()
+    }
+
+    /// Parse the start of the occurrence bound by [`PreEvent::recurrence_pattern`].
+    pub fn parse_recurrence_id(uri: &str, recurrence_id: Option<&str>) -> Option<Zoned> {
+        let recurrence_id = recurrence_id.filter(|recurrence_id| !recurrence_id.is_empty())?;
+        let parsed = Self::parse_occurrence_start(recurrence_id);
+        if parsed.is_none() {
+            warn!("Event \"{uri}\" overrides an invalid occurrence \"{recurrence_id}\"");
+        }
+        parsed
+    }
+
+    /// Parse the start of an occurrence, either a date or a zoned datetime.
+    fn parse_occurrence_start(start: &str) -> Option<Zoned> {
+        start
+            .parse::<Zoned>()
+            .ok()
+            .or_else(|| start.parse::<jiff::civil::Date>().ok().map(Zoned::from))
+    }
+
+    /// The SPARQL pattern binding `?exdates` to the exception dates of the event bound to the
+    /// given variable or parameter, separated by commas.
+    pub fn exdates_pattern(features: &FeatureSet, variable: &str) -> String {
+        if !features.exdate {
+            return String::new();
+        }
+
+        // Parameters cannot be projected, and only match a single event anyway
+        let (projection, group_by) = if variable.starts_with('~') {
+            (String::new(), String::new())
+        } else {
+            (variable.to_string(), format!("GROUP BY {variable}"))
+        };
+
+        format!(
+            "OPTIONAL {{
+                SELECT {projection} (GROUP_CONCAT(?exdate; SEPARATOR=\",\") AS ?exdates)
+                WHERE {{ {variable} ccm:exdate ?exdate }}
+                {group_by}
+            }}"
+        )
+    }
+
+    /// Parse the exception dates bound by [`PreEvent::exdates_pattern`].
+    ///
+    /// Exception dates are either dates or zoned datetimes, and invalid ones are skipped.
+    pub fn parse_exdates(uri: &str, exdates: Option<&str>) -> Vec<Zoned> {
+        let mut exdates = exdates
+            .unwrap_or_default()
+            .split(',')
+            .filter(|exdate| !exdate.is_empty())
+            .filter_map(|exdate| {
+                let parsed = Self::parse_occurrence_start(exdate);
+                if parsed.is_none() {
+                    warn!("Event \"{uri}\" has an invalid exception date \"{exdate}\"");
+                }
+                parsed
+            })
+            .collect::<Vec<_>>();
+        exdates.sort_by_key(|exdate| exdate.0.timestamp());
+        exdates
+    }
+
+    /// The SPARQL pattern binding `?attendees` to the attendees of the event bound to the given
+    /// variable or parameter, each as its email and participation status separated by a space,
+    /// separated by commas.
+    pub fn attendees_pattern(features: &FeatureSet, variable: &str) -> String {
+        if !features.attendee {
+            return String::new();
+        }
+
+        // Parameters cannot be projected, and only match a single event anyway
+        let (projection, group_by) = if variable.starts_with('~') {
+            (String::new(), String::new())
+        } else {
+            (variable.to_string(), format!("GROUP BY {variable}"))
+        };
+
+        format!(
+            "OPTIONAL {{
+                SELECT {projection}
+                    (GROUP_CONCAT(CONCAT(?email, \" \", COALESCE(?partstat, \"\")); SEPARATOR=\",\")
+                        AS ?attendees)
+                WHERE {{
+                    {variable} ccm:attendee ?attendee .
+                    ?attendee ccm:attendeeEmail ?email .
+                    OPTIONAL {{ ?attendee ccm:participationStatus ?partstat }}
+                }}
+                {group_by}
+            }}"
+        )
+    }
+
+    /// Parse the attendees bound by [`PreEvent::attendees_pattern`].
+    pub fn parse_attendees(attendees: Option<&str>) -> Vec<Attendee> {
+        attendees
+            .unwrap_or_default()
+            .split(',')
+            .filter(|attendee| !attendee.is_empty())
+            .map(|attendee| {
+                let (email, status) = attendee.split_once(' ').unwrap_or((attendee, ""));
+                Attendee {
+                    email: email.to_string(),
+                    status: ParticipationStatus::from_db(status),
+                }
+            })
+            .collect()
+    }
+
+    /// Parse the time frame of this event.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the start or end of the event are invalid. Use
+    /// [`PreEvent::is_valid`] first for events coming from the backend.
+    pub fn timeframe(&self) -> Timeframe {
+        self.parse_timeframe().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Check that the time frame of this event parses and is plausible, warning otherwise.
+    ///
+    /// Events failing this check should be skipped rather than loaded.
+    pub fn is_valid(&self) -> bool {
+        match self.parse_timeframe() {
+            Ok(timeframe) if timeframe.is_plausible() => true,
+            Ok(timeframe) => {
+                warn!(
+                    "Event \"{}\" has an implausible time frame, from {} to {}",
+                    self.uri,
+                    timeframe.start(),
+                    timeframe.end()
+                );
+                false
+            }
+            Err(err) => {
+                warn!("{err}");
+                false
+            }
+        }
+    }
+
+    /// Fix the time frame of this event if its end comes before its start, warning about it.
+    pub fn fix_malformed_times(&mut self, policy: MalformedTimePolicy) {
+        let Ok(timeframe) = self.parse_timeframe() else {
+            return;
+        };
+        if timeframe.end().0.timestamp() >= timeframe.start().0.timestamp() {
+            return;
+        }
+
+        warn!(
+            "Event \"{}\" ends at {} before it starts at {}, applying {policy:?}",
+            self.uri, self.end, self.start
+        );
+        match policy {
+            MalformedTimePolicy::Swap => std::mem::swap(&mut self.start, &mut self.end),
+            MalformedTimePolicy::ClampEnd => self.end.clone_from(&self.start),
+        }
+    }
+
+    fn parse_timeframe(&self) -> Result<Timeframe, String> {
+        let uri = &self.uri;
+
+        if self.all_day {
+            let start = self
+                .start
+                .parse::<jiff::civil::Date>()
+                .map_err(|_| format!("Event \"{uri}\" has an invalid start date"))?
+                .into();
+            let end = self
+                .end
+                .parse::<jiff::civil::Date>()
+                .map_err(|_| format!("Event \"{uri}\" has an invalid end date"))?
+                .into();
+
+            Ok(Timeframe::new(true, start, end))
+        } else {
+            let start = self
+                .start
+                .parse::<Zoned>()
+                .map_err(|_| format!("Event \"{uri}\" has an invalid start date"))?;
+            let end = self
+                .end
+                .parse::<Zoned>()
+                .map_err(|_| format!("Event \"{uri}\" has an invalid end date"))?;
+
+            Ok(Timeframe::new(false, start, end))
+        }
+    }
+
     /// Retrieves an event resource from a URI.
     ///
     /// # Panics
     ///
     /// This function may panic if the given URI is invalid or does not point to an event resource.
-    pub fn from_uri(read_connection: &SparqlConnection, uri: &str) -> Result<Self, ()> {
+    pub fn from_uri(
+        read_connection: &SparqlConnection,
+        features: &FeatureSet,
+        uri: &str,
+        cancellable: &gio::Cancellable,
+    ) -> Result<Self, ()> {
+        let transparency_pattern = if features.transparency {
+            "OPTIONAL { ~uri ccm:transparency ?transparency }"
+        } else {
+            ""
+        };
+        let exdates_pattern = Self::exdates_pattern(features, "~uri");
+        let attendees_pattern = Self::attendees_pattern(features, "~uri");
+        let recurrence_pattern = Self::recurrence_pattern(features, "~uri");
+
         let statement = read_connection
             .query_statement(
-                "SELECT ?name ?description ?calendar ?all_day ?start ?end
-                WHERE {
-                    ~uri a ccm:Event ;
-                        ccm:calendar ?calendar ;
-                        ccm:eventName ?name ;
-                        ccm:eventDescription ?description  ;
-                        ccm:eventAllDay ?all_day ;
-                        ccm:eventStart ?start ;
-                        ccm:eventEnd ?end .
-                }",
-                None::<&gio::Cancellable>,
+                &format!(
+                    "SELECT ?name ?description ?calendar ?all_day ?start ?end ?color ?transparency
+                        ?exdates ?attendees ?recurrence ?recurrence_of ?recurrence_id
+                    WHERE {{
+                        ~uri a ccm:Event ;
+                            ccm:calendar ?calendar ;
+                            ccm:eventName ?name ;
+                            ccm:eventAllDay ?all_day ;
+                            ccm:eventStart ?start ;
+                            ccm:eventEnd ?end .
+                        OPTIONAL {{ ~uri ccm:eventDescription ?description }}
+                        OPTIONAL {{ ~uri ccm:color ?color }}
+                        {transparency_pattern}
+                        {exdates_pattern}
+                        {attendees_pattern}
+                        {recurrence_pattern}
+                    }}"
+                ),
+                Some(cancellable),
             )
             .expect("SPARQL should be valid")
             .expect("SPARQL should be valid");
         statement.bind_string("uri", uri);
 
-        let cursor = match statement.execute(None::<&gio::Cancellable>) {
+        let cursor = match statement.execute(Some(cancellable)) {
             Ok(cursor) => cursor,
             Err(err) => {
                 error!("Failed to create event: {err:?}");
@@ -45,16 +376,13 @@ impl PreEvent {
             }
         };
 
-        match cursor.next(None::<&gio::Cancellable>) {
+        match cursor.next(Some(cancellable)) {
             Ok(true) => {
                 let name = cursor
                     .string(0)
                     .expect("Query should return an event name")
                     .to_string();
-                let description = cursor
-                    .string(1)
-                    .expect("Query should return an event description")
-                    .to_string();
+                let description = cursor.string(1).unwrap_or_default().to_string();
                 let calendar_uri = cursor
                     .string(2)
                     .expect("Query should return a calendar URI")
@@ -68,7 +396,14 @@ impl PreEvent {
                     .string(5)
                     .expect("Query should return a calendar URI")
                     .to_string();
-                let calendar = Self {
+                let color = cursor.string(6).and_then(|color| color.parse().ok());
+                let transparency = EventTransparency::from_db(cursor.string(7).as_deref());
+                let exdates = Self::parse_exdates(uri, cursor.string(8).as_deref());
+                let attendees = Self::parse_attendees(cursor.string(9).as_deref());
+                let recurrence = Self::parse_recurrence(uri, cursor.string(10).as_deref());
+                let recurrence_of = cursor.string(11).map(|uri| uri.to_string());
+                let recurrence_id = Self::parse_recurrence_id(uri, cursor.string(12).as_deref());
+                let event = Self {
                     uri: uri.to_string(),
                     calendar_uri,
                     name,
@@ -76,9 +411,20 @@ impl PreEvent {
                     all_day,
                     start,
                     end,
+                    color,
+                    transparency,
+                    exdates,
+                    attendees,
+                    recurrence,
+                    recurrence_of,
+                    recurrence_id,
                 };
 
-                Ok(calendar)
+                if !event.is_valid() {
+                    return Err(());
+                }
+
+                Ok(event)
             }
             Ok(false) => {
                 error!("Resource {uri} was created but is not found in database");
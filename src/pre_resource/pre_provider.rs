@@ -1,17 +1,87 @@
-use tsparql::SparqlConnection;
+use gdk::gio;
+use tracing::error;
+use tsparql::{SparqlConnection, prelude::*};
+
+use crate::FeatureSet;
 
 pub struct PreProvider {
     pub uri: String,
     pub name: String,
+    /// The identity of the account of the provider, usually an email address.
+    pub account_identity: Option<String>,
 }
 
 impl PreProvider {
+    /// The SPARQL pattern binding `?account_identity` for the provider bound to the given
+    /// variable or parameter.
+    pub fn account_identity_pattern(features: &FeatureSet, variable: &str) -> String {
+        if !features.account_identity {
+            return String::new();
+        }
+
+        format!("OPTIONAL {{ {variable} ccm:accountIdentity ?account_identity }}")
+    }
+
     /// Retrieves a provider resource from a URI.
     ///
     /// # Panics
     ///
     /// This function may panic if the given URI is invalid or does not point to a provider resource.
-    pub fn from_uri(_read_connection: &SparqlConnection, _uri: &str) -> Result<Self, ()> {
-        todo!()
+    pub fn from_uri(
+        read_connection: &SparqlConnection,
+        features: &FeatureSet,
+        uri: &str,
+        cancellable: &gio::Cancellable,
+    ) -> Result<Self, ()> {
+        let account_identity_pattern = Self::account_identity_pattern(features, "~uri");
+
+        let statement = read_connection
+            .query_statement(
+                &format!(
+                    "SELECT ?name ?account_identity
+                    WHERE {{
+                        ~uri a ccm:Provider ;
+                            ccm:providerName ?name .
+                        {account_identity_pattern}
+                    }}"
+                ),
+                Some(cancellable),
+            )
+            .expect("SPARQL should be valid")
+            .expect("SPARQL should be valid");
+        statement.bind_string("uri", uri);
+
+        let cursor = match statement.execute(Some(cancellable)) {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                error!("Failed to create provider: {err:?}");
+                return Err(());
+            }
+        };
+
+        match cursor.next(Some(cancellable)) {
+            Ok(true) => {
+                let name = cursor
+                    .string(0)
+                    .expect("Query should return a provider name")
+                    .to_string();
+                let account_identity = cursor.string(1).map(|identity| identity.to_string());
+                let provider = Self {
+                    uri: uri.to_string(),
+                    name,
+                    account_identity,
+                };
+
+                Ok(provider)
+            }
+            Ok(false) => {
+                error!("Resource {uri} was created but is not found in database");
+                Err(())
+            }
+            Err(e) => {
+                error!("Encountered glib error: {}", e);
+                Err(())
+            }
+        }
     }
 }
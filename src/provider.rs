@@ -2,25 +2,50 @@ use std::cell::{OnceCell, RefCell};
 
 use gdk::{
     gio::ListStore,
-    glib::{self, Object},
+    glib::{self, Object, clone},
     prelude::*,
     subclass::prelude::*,
 };
 
+use tracing::info;
+
 use crate::{Collection, Manager};
 
+/// The kind of backend behind a provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    /// Calendars stored on this device only.
+    Local,
+    /// Calendars synchronized with a CalDAV server.
+    CalDav,
+}
+
+impl ProviderKind {
+    /// The identifier of this kind, as understood by the write service.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::CalDav => "caldav",
+        }
+    }
+}
+
 mod imp {
     use super::*;
 
     #[derive(Debug, Default, glib::Properties)]
     #[properties(wrapper_type = super::Provider)]
     pub struct Provider {
-        #[property(get, construct_only)]
-        manager: OnceCell<Manager>,
+        /// The manager of this provider, `None` for detached providers.
+        #[property(get, construct_only, nullable)]
+        manager: RefCell<Option<Manager>>,
         #[property(get, construct_only)]
         uri: OnceCell<String>,
         #[property(get, set)]
         name: RefCell<String>,
+        /// The identity of the account of this provider, usually an email address.
+        #[property(get, construct_only, nullable)]
+        pub(super) account_identity: RefCell<Option<String>>,
         #[property(get)]
         collections: OnceCell<ListStore>,
     }
@@ -56,16 +81,59 @@ glib::wrapper! {
 
 impl Provider {
     /// Create a provider from its properties.
-    pub(crate) fn new(manager: &Manager, uri: &str, name: &str) -> Self {
+    pub(crate) fn new(
+        manager: &Manager,
+        uri: &str,
+        name: &str,
+        account_identity: Option<&str>,
+    ) -> Self {
         glib::Object::builder()
             .property("manager", manager)
             .property("uri", uri)
             .property("name", name)
+            .property("account_identity", account_identity)
             .build()
     }
 
+    /// Create a provider without a manager, for tests.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn new_detached(uri: &str, name: &str) -> Self {
+        glib::Object::builder()
+            .property("uri", uri)
+            .property("name", name)
+            .build()
+    }
+
+    /// Apply the properties retrieved from the backend, notifying if they changed.
+    pub(crate) fn emit_updated(&self, name: &str, account_identity: Option<&str>) {
+        let uri = self.uri();
+        if name != self.name() {
+            self.set_name(name);
+            info!("Provider {uri} updated to name {name}");
+        }
+        if account_identity != self.imp().account_identity.borrow().as_deref() {
+            self.imp()
+                .account_identity
+                .replace(account_identity.map(str::to_string));
+            info!("Provider {uri} updated to account identity {account_identity:?}");
+            self.notify_account_identity();
+        }
+    }
+
     /// Add a collection to this provider.
     pub(crate) fn add_collection(&self, collection: &Collection) {
         self.imp().collections().append(collection);
+
+        collection.connect_deleted(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |collection| {
+                let index = obj
+                    .collections()
+                    .find(collection)
+                    .expect("Collection should be found");
+                obj.collections().remove(index);
+            }
+        ));
     }
 }
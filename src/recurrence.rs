@@ -0,0 +1,306 @@
+use std::{fmt, str::FromStr};
+
+use crate::{Event, Instant, Timeframe, Zoned};
+
+/// How often a recurring event repeats, before its interval is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    /// The span between the first occurrence and the `n`th one after it.
+    fn span(self, n: i64) -> Option<jiff::Span> {
+        let span = jiff::Span::new();
+        match self {
+            Self::Daily => span.try_days(n),
+            Self::Weekly => span.try_weeks(n),
+            Self::Monthly => span.try_months(n),
+            Self::Yearly => span.try_years(n),
+        }
+        .ok()
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Daily => "DAILY",
+            Self::Weekly => "WEEKLY",
+            Self::Monthly => "MONTHLY",
+            Self::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// The rule an event recurs with, as stored in `ccm:eventRecurrence`.
+///
+/// Its string form is an iCalendar `RRULE` value, such as `FREQ=WEEKLY;INTERVAL=2;COUNT=10`.
+/// Only the `FREQ`, `INTERVAL`, `COUNT` and `UNTIL` parts are supported, and rules with other
+/// parts fail to parse rather than being expanded wrongly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    /// The number of periods between two occurrences, at least 1.
+    pub interval: u32,
+    /// The number of occurrences, including the first one.
+    pub count: Option<u32>,
+    /// The last start an occurrence may have, inclusive.
+    pub until: Option<Instant>,
+}
+
+impl RecurrenceRule {
+    /// The starts of the occurrences of a recurrence whose first occurrence starts at `first`,
+    /// in order, including `first`.
+    ///
+    /// Like in iCalendar, monthly and yearly occurrences falling on a day that does not exist,
+    /// such as the 31st of a shorter month, are skipped rather than moved, and do not count
+    /// towards [`RecurrenceRule::count`]. Exception dates and filters are not applied.
+    pub fn starts<'a>(&'a self, first: &'a Zoned) -> impl Iterator<Item = Zoned> + 'a {
+        let mut period = 0_i64;
+        let mut emitted = 0_u32;
+
+        std::iter::from_fn(move || {
+            loop {
+                if self.count.is_some_and(|count| emitted >= count) {
+                    return None;
+                }
+
+                let span = self
+                    .frequency
+                    .span(period.checked_mul(i64::from(self.interval))?)?;
+                period += 1;
+                let start = first.0.checked_add(span).ok()?;
+
+                // Months and years are clamped by jiff, so the 31st of January plus one month
+                // would be the last day of February
+                let clamped = match self.frequency {
+                    Frequency::Daily | Frequency::Weekly => false,
+                    Frequency::Monthly => start.day() != first.0.day(),
+                    Frequency::Yearly => {
+                        start.day() != first.0.day() || start.month() != first.0.month()
+                    }
+                };
+                if clamped {
+                    continue;
+                }
+
+                let past_until = match &self.until {
+                    None => false,
+                    Some(Instant::Date(until)) => start.date() > *until,
+                    Some(Instant::Zoned(until)) => start.timestamp() > until.0.timestamp(),
+                };
+                if past_until {
+                    return None;
+                }
+
+                emitted += 1;
+                return Some(Zoned(start));
+            }
+        })
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+
+        for part in s.trim().trim_start_matches("RRULE:").split(';') {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid recurrence rule part \"{part}\""))?;
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Unsupported recurrence frequency \"{value}\"")),
+                    });
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("Invalid recurrence interval \"{value}\""))?;
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid recurrence count \"{value}\""))?,
+                    );
+                }
+                "UNTIL" => until = Some(parse_until(value)?),
+                _ => return Err(format!("Unsupported recurrence rule part \"{part}\"")),
+            }
+        }
+
+        Ok(Self {
+            frequency: frequency.ok_or("Recurrence rule has no frequency")?,
+            interval,
+            count,
+            until,
+        })
+    }
+}
+
+/// Parse the `UNTIL` part of a recurrence rule, either a date such as `20240601` or a UTC
+/// datetime such as `20240601T090000Z`.
+fn parse_until(value: &str) -> Result<Instant, String> {
+    let invalid = || format!("Invalid recurrence end \"{value}\"");
+
+    if let Some(datetime) = value.strip_suffix('Z') {
+        let datetime =
+            jiff::civil::DateTime::strptime("%Y%m%dT%H%M%S", datetime).map_err(|_| invalid())?;
+        let zoned = datetime
+            .to_zoned(jiff::tz::TimeZone::UTC)
+            .map_err(|_| invalid())?;
+        Ok(Instant::Zoned(Zoned(zoned)))
+    } else {
+        jiff::civil::Date::strptime("%Y%m%d", value)
+            .map(Instant::Date)
+            .map_err(|_| invalid())
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.frequency.as_str())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        match &self.until {
+            None => {}
+            Some(Instant::Date(until)) => write!(f, ";UNTIL={}", until.strftime("%Y%m%d"))?,
+            Some(Instant::Zoned(until)) => write!(
+                f,
+                ";UNTIL={}",
+                until
+                    .0
+                    .with_time_zone(jiff::tz::TimeZone::UTC)
+                    .strftime("%Y%m%dT%H%M%SZ")
+            )?,
+        }
+        Ok(())
+    }
+}
+
+/// A single occurrence of an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    /// The event this occurrence comes from, either the recurring event or the event overriding
+    /// this occurrence.
+    pub event: Event,
+    pub timeframe: Timeframe,
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::date;
+
+    use super::*;
+
+    fn zoned(s: &str) -> Zoned {
+        s.parse().unwrap()
+    }
+
+    fn starts(rule: &str, first: &str, n: usize) -> Vec<String> {
+        let rule = rule.parse::<RecurrenceRule>().unwrap();
+        rule.starts(&zoned(first))
+            .take(n)
+            .map(|start| start.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn count_limits_the_occurrences() {
+        assert_eq!(
+            starts(
+                "FREQ=DAILY;INTERVAL=2;COUNT=3",
+                "2024-06-01T09:00:00+02:00[Europe/Paris]",
+                10
+            ),
+            [
+                "2024-06-01T09:00:00+02:00[Europe/Paris]",
+                "2024-06-03T09:00:00+02:00[Europe/Paris]",
+                "2024-06-05T09:00:00+02:00[Europe/Paris]",
+            ]
+        );
+    }
+
+    #[test]
+    fn until_is_inclusive() {
+        assert_eq!(
+            starts(
+                "FREQ=WEEKLY;UNTIL=20240615T070000Z",
+                "2024-06-01T09:00:00+02:00[Europe/Paris]",
+                10
+            ),
+            [
+                "2024-06-01T09:00:00+02:00[Europe/Paris]",
+                "2024-06-08T09:00:00+02:00[Europe/Paris]",
+                "2024-06-15T09:00:00+02:00[Europe/Paris]",
+            ]
+        );
+        assert_eq!(
+            starts("FREQ=DAILY;UNTIL=20240602", "2024-06-01T09:00:00[UTC]", 10).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn daily_occurrences_keep_their_wall_clock_time_across_dst() {
+        assert_eq!(
+            starts("FREQ=DAILY", "2024-03-30T09:00:00+01:00[Europe/Paris]", 2),
+            [
+                "2024-03-30T09:00:00+01:00[Europe/Paris]",
+                "2024-03-31T09:00:00+02:00[Europe/Paris]",
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_occurrences_skip_missing_days() {
+        let rule = "FREQ=MONTHLY;COUNT=3".parse::<RecurrenceRule>().unwrap();
+        let dates = rule
+            .starts(&date(2024, 1, 31).into())
+            .map(|start| start.date())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dates,
+            [date(2024, 1, 31), date(2024, 3, 31), date(2024, 5, 31)]
+        );
+    }
+
+    #[test]
+    fn unsupported_rules_are_rejected() {
+        assert!("FREQ=WEEKLY;BYDAY=MO,WE".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=HOURLY".parse::<RecurrenceRule>().is_err());
+        assert!("INTERVAL=2".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;INTERVAL=0".parse::<RecurrenceRule>().is_err());
+    }
+
+    #[test]
+    fn rules_round_trip_through_their_string_form() {
+        for rule in [
+            "FREQ=DAILY",
+            "FREQ=WEEKLY;INTERVAL=2;COUNT=10",
+            "FREQ=YEARLY;UNTIL=20301231",
+            "FREQ=MONTHLY;UNTIL=20240615T070000Z",
+        ] {
+            assert_eq!(rule.parse::<RecurrenceRule>().unwrap().to_string(), rule);
+        }
+    }
+}
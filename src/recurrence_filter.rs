@@ -0,0 +1,68 @@
+use jiff::civil::{Date, Weekday};
+
+/// Restrictions on the days the occurrences of a recurring event may fall on.
+///
+/// Occurrences falling on a rejected day are skipped rather than moved, so a daily event filtered
+/// to working days recurs on weekdays only. Like exception dates, skipped occurrences still count
+/// towards the `COUNT` of the recurrence rule. Filters are applied by
+/// [`Event::occurrences_in_range`](crate::Event::occurrences_in_range).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecurrenceFilter {
+    /// Skip the occurrences falling on a Saturday or a Sunday.
+    pub skip_weekends: bool,
+    /// Skip the occurrences falling on one of these dates.
+    pub holidays: Vec<Date>,
+}
+
+impl RecurrenceFilter {
+    /// A filter keeping the occurrences on weekdays only.
+    pub fn working_days() -> Self {
+        Self {
+            skip_weekends: true,
+            holidays: Vec::new(),
+        }
+    }
+
+    /// Whether an occurrence falling on this date is kept.
+    pub fn accepts(&self, date: Date) -> bool {
+        if self.skip_weekends && matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday) {
+            return false;
+        }
+
+        !self.holidays.contains(&date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::date;
+
+    use super::*;
+
+    #[test]
+    fn default_filter_accepts_every_day() {
+        let filter = RecurrenceFilter::default();
+        assert!(filter.accepts(date(2024, 6, 1)));
+        assert!(filter.accepts(date(2024, 6, 3)));
+    }
+
+    #[test]
+    fn working_days_skip_weekends() {
+        let filter = RecurrenceFilter::working_days();
+        // The 3rd of June 2024 is a Monday
+        let week = (3..=9)
+            .map(|day| filter.accepts(date(2024, 6, day)))
+            .collect::<Vec<_>>();
+        assert_eq!(week, [true, true, true, true, true, false, false]);
+    }
+
+    #[test]
+    fn holidays_are_skipped() {
+        let filter = RecurrenceFilter {
+            skip_weekends: true,
+            holidays: vec![date(2024, 12, 25)],
+        };
+        assert!(!filter.accepts(date(2024, 12, 25)));
+        assert!(filter.accepts(date(2024, 12, 24)));
+    }
+}
@@ -1,5 +1,17 @@
+use gdk::glib;
+
 use crate::{Calendar, Collection, Event, Provider};
 
+/// How a resource changed, as reported by the `resource-changed` signal of the manager.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "ResourceChangeKind")]
+pub enum ResourceChangeKind {
+    #[default]
+    Created,
+    Updated,
+    Deleted,
+}
+
 #[derive(Debug, Clone)]
 pub enum Resource {
     Provider(Provider),
@@ -0,0 +1,61 @@
+use gdk::RGBA;
+
+use crate::{Calendar, Event};
+
+/// A snapshot of the properties of an event, that can be sent to other threads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventData {
+    pub uri: String,
+    pub calendar_uri: String,
+    pub name: String,
+    pub description: String,
+    pub all_day: bool,
+    pub start: jiff::Zoned,
+    pub end: jiff::Zoned,
+    /// The color overriding the one of the calendar, if any.
+    pub color: Option<RGBA>,
+}
+
+/// A snapshot of the properties of a calendar, that can be sent to other threads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarData {
+    pub uri: String,
+    pub collection_uri: String,
+    pub name: String,
+    pub color: RGBA,
+}
+
+impl Event {
+    /// Take a snapshot of the properties of this event.
+    ///
+    /// Unlike the event itself, the snapshot can be sent to another thread. It is not updated when
+    /// the event changes.
+    pub fn to_data(&self) -> EventData {
+        let timeframe = self.timeframe().unwrap_or_default();
+        EventData {
+            uri: self.uri(),
+            calendar_uri: self.calendar().uri(),
+            name: self.name(),
+            description: self.description(),
+            all_day: timeframe.all_day(),
+            start: timeframe.start().0,
+            end: timeframe.end().0,
+            color: self.color(),
+        }
+    }
+}
+
+impl Calendar {
+    /// Take a snapshot of the properties of this calendar.
+    ///
+    /// Unlike the calendar itself, the snapshot can be sent to another thread. It is not updated
+    /// when the calendar changes.
+    pub fn to_data(&self) -> CalendarData {
+        CalendarData {
+            uri: self.uri(),
+            collection_uri: self.collection().uri(),
+            name: self.name(),
+            color: self.color().unwrap_or(RGBA::BLACK),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::{cell::OnceCell, sync::LazyLock};
+
+use gdk::{
+    glib::{self, Object, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::ResourceWatcher)]
+    pub struct ResourceWatcher {
+        #[property(get, construct_only)]
+        uri: OnceCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ResourceWatcher {
+        const NAME: &'static str = "ResourceWatcher";
+        type Type = super::ResourceWatcher;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for ResourceWatcher {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
+                vec![
+                    Signal::builder("changed").build(),
+                    Signal::builder("deleted").build(),
+                ]
+            });
+            SIGNALS.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// An object notified of the changes of a single resource.
+    ///
+    /// Watchers are created with [`Manager::watch`](crate::Manager::watch).
+    pub struct ResourceWatcher(ObjectSubclass<imp::ResourceWatcher>);
+}
+
+impl ResourceWatcher {
+    /// Create a watcher for the resource at the given URI.
+    pub(crate) fn new(uri: &str) -> Self {
+        glib::Object::builder().property("uri", uri).build()
+    }
+
+    /// Signal that the watched resource was created or changed.
+    pub(crate) fn emit_changed(&self) {
+        self.emit_by_name::<()>("changed", &[]);
+    }
+
+    /// Connect to the signal emitted when the watched resource is created or changed.
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    /// Signal that the watched resource was deleted.
+    pub(crate) fn emit_deleted(&self) {
+        self.emit_by_name::<()>("deleted", &[]);
+    }
+
+    /// Connect to the signal emitted when the watched resource is deleted.
+    pub fn connect_deleted<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "deleted",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use gdk::{gio, glib};
+
+/// How failed writes are retried.
+///
+/// Only transient failures, such as timeouts or a lost connection to the bus, are retried.
+/// Errors returned by the provider itself are reported immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of retries after the first attempt.
+    pub max_retries: u32,
+    /// The delay before the first retry. It doubles after each retry.
+    pub initial_delay: Duration,
+    /// The upper bound of the delay between two retries.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub const NONE: Self = Self {
+        max_retries: 0,
+        initial_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    };
+
+    /// The delay to wait before the given retry, starting from 0.
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay)
+    }
+
+    /// Whether the given error is worth retrying.
+    pub(crate) fn is_transient(err: &glib::Error) -> bool {
+        if let Some(err) = err.kind::<gio::DBusError>() {
+            return matches!(
+                err,
+                gio::DBusError::NoReply
+                    | gio::DBusError::Timeout
+                    | gio::DBusError::TimedOut
+                    | gio::DBusError::NoServer
+                    | gio::DBusError::NoNetwork
+                    | gio::DBusError::Disconnected
+                    | gio::DBusError::ServiceUnknown
+                    | gio::DBusError::LimitsExceeded
+            );
+        }
+
+        if let Some(err) = err.kind::<gio::IOErrorEnum>() {
+            return matches!(
+                err,
+                gio::IOErrorEnum::TimedOut
+                    | gio::IOErrorEnum::Closed
+                    | gio::IOErrorEnum::Busy
+                    | gio::IOErrorEnum::WouldBlock
+                    | gio::IOErrorEnum::BrokenPipe
+                    | gio::IOErrorEnum::NotConnected
+            );
+        }
+
+        false
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
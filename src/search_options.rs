@@ -0,0 +1,72 @@
+/// Options tweaking how events are searched.
+///
+/// Searching relies on the full-text search of the database, which matches whole words. Its
+/// tokenizer ignores case and, with the default configuration, diacritics, so "Reunion" matches
+/// "réunion".
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Match words starting with the last word of the query, which is what search-as-you-type
+    /// interfaces expect.
+    pub prefix_match: bool,
+}
+
+impl SearchOptions {
+    /// Normalize a query according to these options.
+    ///
+    /// The query is trimmed and lowercased. Returns `None` if nothing is left to search for.
+    pub(crate) fn normalize_query(&self, query: &str) -> Option<String> {
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        if query.is_empty() {
+            return None;
+        }
+
+        let mut query = query.to_lowercase();
+        if self.prefix_match && !query.ends_with('*') {
+            query.push('*');
+        }
+
+        Some(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_is_collapsed() {
+        let options = SearchOptions::default();
+        assert_eq!(
+            options.normalize_query("  team \t meeting\n"),
+            Some("team meeting".to_string())
+        );
+        assert_eq!(options.normalize_query(""), None);
+        assert_eq!(options.normalize_query(" \t\n "), None);
+    }
+
+    #[test]
+    fn case_is_lowered() {
+        let options = SearchOptions::default();
+        assert_eq!(
+            options.normalize_query("Team MEETING"),
+            Some("team meeting".to_string())
+        );
+    }
+
+    #[test]
+    fn accents_are_kept_for_the_tokenizer() {
+        let options = SearchOptions::default();
+        assert_eq!(
+            options.normalize_query("RÉUNION Équipe"),
+            Some("réunion équipe".to_string())
+        );
+    }
+
+    #[test]
+    fn prefix_match_adds_a_single_wildcard() {
+        let options = SearchOptions { prefix_match: true };
+        assert_eq!(options.normalize_query("Meet "), Some("meet*".to_string()));
+        assert_eq!(options.normalize_query("meet*"), Some("meet*".to_string()));
+        assert_eq!(options.normalize_query("   "), None);
+    }
+}
@@ -0,0 +1,46 @@
+use std::cell::OnceCell;
+
+use gdk::{glib, prelude::*, subclass::prelude::*};
+
+use crate::Event;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::SearchResult)]
+    pub struct SearchResult {
+        #[property(get, construct_only)]
+        event: OnceCell<Event>,
+        /// An excerpt of the event around the words matching the query, with the matches
+        /// surrounded by the markers given to the search.
+        #[property(get, construct_only)]
+        snippet: OnceCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SearchResult {
+        const NAME: &'static str = "SearchResult";
+        type Type = super::SearchResult;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for SearchResult {}
+}
+
+glib::wrapper! {
+    /// An event matching a search, with the excerpt it matched in.
+    ///
+    /// Search results are created with
+    /// [`Manager::search_events_with_snippets`](crate::Manager::search_events_with_snippets).
+    pub struct SearchResult(ObjectSubclass<imp::SearchResult>);
+}
+
+impl SearchResult {
+    pub(crate) fn new(event: &Event, snippet: &str) -> Self {
+        glib::Object::builder()
+            .property("event", event)
+            .property("snippet", snippet)
+            .build()
+    }
+}
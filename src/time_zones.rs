@@ -0,0 +1,19 @@
+use jiff::tz::TimeZone;
+
+/// The IANA identifiers of the time zones known to the time zone database, such as
+/// `Europe/Paris`, sorted alphabetically.
+pub fn available_timezones() -> Vec<String> {
+    let mut names: Vec<String> = jiff::tz::db()
+        .available()
+        .map(|name| name.as_str().to_owned())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+/// The time zone of the system, to use as default for new events.
+///
+/// Falls back to UTC if the time zone of the system cannot be determined.
+pub fn system_timezone() -> TimeZone {
+    TimeZone::try_system().unwrap_or(TimeZone::UTC)
+}
@@ -1,17 +1,43 @@
 use std::{
+    borrow::Cow,
     cell::{Cell, RefCell},
     fmt,
+    ops::RangeInclusive,
     str::FromStr,
 };
 
 use gdk::{
-    glib::{self, Object},
+    glib::{self, Object, Variant, VariantTy},
     prelude::*,
     subclass::prelude::*,
 };
+use gettextrs::{gettext, ngettext};
 
+/// The years event times must fall in to be accepted from the backend.
+///
+/// Times outside of this range are almost always garbage, and would break date arithmetic.
+pub const PLAUSIBLE_YEARS: RangeInclusive<i16> = 1900..=2200;
+
+/// How to fix events whose end comes before their start.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "MalformedTimePolicy")]
+pub enum MalformedTimePolicy {
+    /// Swap the start and the end, assuming the backend mixed them up.
+    #[default]
+    Swap,
+    /// Move the end to the start, making the event instantaneous.
+    ClampEnd,
+}
+
+/// An instant in a named time zone.
+///
+/// Its string form, used both to write to and read from the database, is the RFC 9557 form
+/// produced by jiff, which carries the IANA time zone name alongside the offset, for example
+/// `2024-06-01T09:00:00-04:00[America/New_York]`. Parsing that form gives back the same time zone
+/// rather than a fixed offset, so recurrences and DST transitions are preserved across a round
+/// trip.
 #[derive(Clone, Debug, Default, PartialEq, Eq, glib::Boxed)]
-#[boxed_type(name = "Zoned")]
+#[boxed_type(name = "Zoned", nullable)]
 pub struct Zoned(pub jiff::Zoned);
 
 impl fmt::Display for Zoned {
@@ -28,6 +54,110 @@ impl FromStr for Zoned {
     }
 }
 
+impl Zoned {
+    /// The same instant, expressed in another time zone.
+    pub fn with_time_zone(&self, tz: &jiff::tz::TimeZone) -> Self {
+        Zoned(self.0.with_time_zone(tz.clone()))
+    }
+
+    /// The civil date of this instant, in its own time zone.
+    ///
+    /// All-day instants are stored at midnight UTC, so this is their date as well.
+    pub fn date(&self) -> jiff::civil::Date {
+        self.0.date()
+    }
+
+    /// The day of the week of this instant, in its own time zone.
+    pub fn weekday(&self) -> jiff::civil::Weekday {
+        self.0.weekday()
+    }
+
+    /// The ISO 8601 week date of this instant, in its own time zone.
+    pub fn iso_week_date(&self) -> jiff::civil::ISOWeekDate {
+        self.0.date().iso_week_date()
+    }
+
+    /// Whether both instants fall on the same civil date, each in its own time zone.
+    pub fn is_same_day_as(&self, other: &Zoned) -> bool {
+        self.date() == other.date()
+    }
+
+    /// Whether this instant falls on the current date of the system time zone.
+    pub fn is_today(&self) -> bool {
+        self.date() == jiff::Zoned::now().date()
+    }
+
+    /// A translated label describing this instant relative to `now`, such as "in 2 hours" or
+    /// "yesterday".
+    ///
+    /// The difference is rounded to the nearest minute. Under a minute, the label is "now". Under
+    /// an hour it counts minutes, and if it is under 24 hours once rounded to the nearest hour, it
+    /// counts hours. Beyond that it counts calendar days in the time zone of `now`, with
+    /// "tomorrow" and "yesterday" for a single day, and "today" when both fall on the same day
+    /// despite being a day apart, which happens around DST transitions.
+    pub fn humanize_relative(&self, now: &Zoned) -> String {
+        let minutes = now
+            .0
+            .timestamp()
+            .until(self.0.timestamp())
+            .and_then(|span| {
+                span.round(
+                    jiff::SpanRound::new()
+                        .largest(jiff::Unit::Minute)
+                        .smallest(jiff::Unit::Minute)
+                        .mode(jiff::RoundMode::HalfExpand),
+                )
+            })
+            .map(|span| span.get_minutes())
+            .unwrap_or_default();
+
+        let relative = |count: i64, future_singular, future_plural, past_singular, past_plural| {
+            let label = if count > 0 {
+                ngettext(future_singular, future_plural, count.unsigned_abs() as u32)
+            } else {
+                ngettext(past_singular, past_plural, count.unsigned_abs() as u32)
+            };
+            label.replace("{}", &count.abs().to_string())
+        };
+
+        let hours = (minutes.abs() + 30) / 60 * minutes.signum();
+
+        if minutes == 0 {
+            gettext("now")
+        } else if minutes.abs() < 60 {
+            relative(
+                minutes,
+                "in {} minute",
+                "in {} minutes",
+                "{} minute ago",
+                "{} minutes ago",
+            )
+        } else if hours.abs() < 24 {
+            relative(
+                hours,
+                "in {} hour",
+                "in {} hours",
+                "{} hour ago",
+                "{} hours ago",
+            )
+        } else {
+            let date = self.0.with_time_zone(now.0.time_zone().clone()).date();
+            let days = now
+                .0
+                .date()
+                .until(date)
+                .map(|span| i64::from(span.get_days()))
+                .unwrap_or_default();
+            match days {
+                0 => gettext("today"),
+                1 => gettext("tomorrow"),
+                -1 => gettext("yesterday"),
+                _ => relative(days, "in {} day", "in {} days", "{} day ago", "{} days ago"),
+            }
+        }
+    }
+}
+
 impl From<jiff::civil::Date> for Zoned {
     fn from(date: jiff::civil::Date) -> Self {
         Zoned(
@@ -37,6 +167,75 @@ impl From<jiff::civil::Date> for Zoned {
     }
 }
 
+/// A point in time as stored in the database: a civil date for all-day events, or a zoned
+/// datetime for timed ones.
+///
+/// Parsing tries the zoned form first (see [`Zoned`]), then the date form, such as
+/// `2024-06-01`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Instant {
+    Date(jiff::civil::Date),
+    Zoned(Zoned),
+}
+
+/// The form of an [`Instant`], without its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstantKind {
+    Date,
+    Zoned,
+}
+
+impl Instant {
+    /// Whether this instant is a date, as used by all-day events.
+    pub fn is_all_day(&self) -> bool {
+        matches!(self, Self::Date(_))
+    }
+
+    /// The date of this instant, if it is a date.
+    pub fn as_date(&self) -> Option<&jiff::civil::Date> {
+        match self {
+            Self::Date(date) => Some(date),
+            Self::Zoned(_) => None,
+        }
+    }
+
+    /// The zoned datetime of this instant, if it is one.
+    pub fn as_zoned(&self) -> Option<&Zoned> {
+        match self {
+            Self::Date(_) => None,
+            Self::Zoned(zoned) => Some(zoned),
+        }
+    }
+
+    /// Whether this instant is a date or a zoned datetime.
+    pub fn kind(&self) -> InstantKind {
+        match self {
+            Self::Date(_) => InstantKind::Date,
+            Self::Zoned(_) => InstantKind::Zoned,
+        }
+    }
+}
+
+impl fmt::Display for Instant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Date(date) => write!(f, "{date}"),
+            Self::Zoned(zoned) => write!(f, "{zoned}"),
+        }
+    }
+}
+
+impl FromStr for Instant {
+    type Err = jiff::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<Zoned>() {
+            Ok(zoned) => Ok(Self::Zoned(zoned)),
+            Err(_) => s.parse::<jiff::civil::Date>().map(Self::Date),
+        }
+    }
+}
+
 mod imp {
     use super::*;
 
@@ -63,6 +262,11 @@ mod imp {
 }
 
 glib::wrapper! {
+    /// The time span of an event.
+    ///
+    /// Like `DTEND` in iCalendar, the end of a time frame is exclusive. An all-day event on a
+    /// single day starts on that day and ends on the next one, and an all-day event from the 1st
+    /// to the 3rd of a month ends on the 4th.
     pub struct Timeframe(ObjectSubclass<imp::Timeframe>);
 }
 
@@ -75,6 +279,183 @@ impl Timeframe {
             .property("end", end)
             .build()
     }
+
+    /// Create a time frame from its start and end, all-day if both are dates.
+    ///
+    /// Returns `None` if one is a date and the other a zoned datetime.
+    pub fn from_instants(start: Instant, end: Instant) -> Option<Self> {
+        match (start, end) {
+            (Instant::Date(start), Instant::Date(end)) => {
+                Some(Self::new(true, start.into(), end.into()))
+            }
+            (Instant::Zoned(start), Instant::Zoned(end)) => Some(Self::new(false, start, end)),
+            _ => None,
+        }
+    }
+
+    /// The start and end of this time frame, in the form they are stored in the database.
+    ///
+    /// All-day time frames are stored as dates, and other time frames as zoned datetimes keeping
+    /// their IANA time zone name (see [`Zoned`]).
+    pub(crate) fn to_db_strings(&self) -> (String, String) {
+        if self.all_day() {
+            (
+                self.start().date().to_string(),
+                self.end().date().to_string(),
+            )
+        } else {
+            (self.start().to_string(), self.end().to_string())
+        }
+    }
+
+    /// Whether both ends of this time frame fall within [`PLAUSIBLE_YEARS`].
+    pub fn is_plausible(&self) -> bool {
+        PLAUSIBLE_YEARS.contains(&self.start().date().year())
+            && PLAUSIBLE_YEARS.contains(&self.end().date().year())
+    }
+
+    /// The start of this time frame, expressed in the given time zone.
+    pub fn start_in(&self, tz: &jiff::tz::TimeZone) -> Zoned {
+        self.start().with_time_zone(tz)
+    }
+
+    /// The end of this time frame, expressed in the given time zone.
+    pub fn end_in(&self, tz: &jiff::tz::TimeZone) -> Zoned {
+        self.end().with_time_zone(tz)
+    }
+
+    /// The start of this time frame both in its original time zone and in the given one.
+    ///
+    /// This is meant for "9:00 AM PST (12:00 PM EST)" style labels. Formatting is left to the
+    /// caller.
+    pub fn start_in_both(&self, tz: &jiff::tz::TimeZone) -> (Zoned, Zoned) {
+        (self.start(), self.start_in(tz))
+    }
+
+    /// The civil dates covered by this time frame, in the time zone of its start.
+    ///
+    /// The end of an all-day time frame is exclusive, and so is the end of a timed time frame
+    /// ending at midnight. A time frame always covers at least the day it starts on.
+    pub fn days(&self) -> impl Iterator<Item = jiff::civil::Date> {
+        let start = self.start().0;
+        let end = self.end().0.with_time_zone(start.time_zone().clone());
+
+        let first = start.date();
+        let last =
+            if self.all_day() || (end.time() == jiff::civil::Time::midnight() && end > start) {
+                end.date().yesterday().unwrap_or(first)
+            } else {
+                end.date()
+            }
+            .max(first);
+
+        first
+            .series(jiff::Span::new().days(1))
+            .take_while(move |day| *day <= last)
+    }
+
+    /// The number of civil days covered by this time frame, following the same rules as
+    /// [`Timeframe::days`].
+    ///
+    /// This is always at least 1.
+    pub fn day_count(&self) -> i64 {
+        self.days().count() as i64
+    }
+
+    /// Whether this time frame shares some time with another one.
+    ///
+    /// As ends are exclusive, a time frame ending when the other starts does not overlap it.
+    /// All-day time frames are compared by dates, so that they are independent of time zones.
+    pub fn overlaps(&self, other: &Timeframe) -> bool {
+        if self.all_day() && other.all_day() {
+            self.start().date() < other.end().date() && other.start().date() < self.end().date()
+        } else {
+            self.start().0 < other.end().0 && other.start().0 < self.end().0
+        }
+    }
+
+    /// The time elapsed between the start and the end of this time frame.
+    ///
+    /// For all-day time frames, the span is a whole number of days. Otherwise, it is expressed
+    /// in hours and smaller units.
+    pub fn duration(&self) -> jiff::Span {
+        let start = self.start().0;
+        let end = self.end().0;
+
+        if self.all_day() {
+            start
+                .date()
+                .until(end.date())
+                .expect("Span between two dates should be computable")
+        } else {
+            start
+                .until((jiff::Unit::Hour, &end))
+                .expect("Span between two zoned datetimes should be computable")
+        }
+    }
+}
+
+/// Time frames are sent over DBus as `(bsss)`: whether the time frame is all-day, its start, its
+/// end, and the IANA name of its time zone.
+///
+/// All-day time frames have ISO 8601 dates as start and end, such as `2024-06-01`. Other time
+/// frames have civil datetimes, such as `2024-06-01T09:00:00`, expressed in the time zone. Time
+/// frames in a time zone without an IANA name are converted to UTC.
+impl StaticVariantType for Timeframe {
+    fn static_variant_type() -> Cow<'static, VariantTy> {
+        <(bool, String, String, String)>::static_variant_type()
+    }
+}
+
+impl ToVariant for Timeframe {
+    fn to_variant(&self) -> Variant {
+        let mut start = self.start().0;
+        if start.time_zone().iana_name().is_none() {
+            start = start.with_time_zone(jiff::tz::TimeZone::UTC);
+        }
+        let tz = start.time_zone().clone();
+        let zone = tz.iana_name().unwrap_or("UTC").to_string();
+        let end = self.end().0.with_time_zone(tz);
+
+        if self.all_day() {
+            (true, start.date().to_string(), end.date().to_string(), zone).to_variant()
+        } else {
+            (
+                false,
+                start.datetime().to_string(),
+                end.datetime().to_string(),
+                zone,
+            )
+                .to_variant()
+        }
+    }
+}
+
+impl FromVariant for Timeframe {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        let (all_day, start, end, zone) = variant.get::<(bool, String, String, String)>()?;
+
+        if all_day {
+            let start = start.parse::<jiff::civil::Date>().ok()?;
+            let end = end.parse::<jiff::civil::Date>().ok()?;
+
+            Some(Self::new(true, start.into(), end.into()))
+        } else {
+            let tz = jiff::tz::TimeZone::get(&zone).ok()?;
+            let start = start
+                .parse::<jiff::civil::DateTime>()
+                .ok()?
+                .to_zoned(tz.clone())
+                .ok()?;
+            let end = end
+                .parse::<jiff::civil::DateTime>()
+                .ok()?
+                .to_zoned(tz)
+                .ok()?;
+
+            Some(Self::new(false, Zoned(start), Zoned(end)))
+        }
+    }
 }
 
 impl Default for Timeframe {
@@ -82,3 +463,299 @@ impl Default for Timeframe {
         Self::new(false, Zoned::default(), Zoned::default())
     }
 }
+
+/// The first and last days, both inclusive, of the week containing the given day.
+pub fn week_range(
+    day: jiff::civil::Date,
+    week_start: jiff::civil::Weekday,
+) -> (jiff::civil::Date, jiff::civil::Date) {
+    let offset = day.weekday().since(week_start);
+    let start = day.saturating_sub(jiff::Span::new().days(offset));
+    let end = start.saturating_add(jiff::Span::new().days(6));
+    (start, end)
+}
+
+/// The first and last days, both inclusive, of a month grid.
+///
+/// The grid is made of the whole weeks spanning the month of the given day, so it includes the
+/// trailing days of the previous month and the leading days of the next one.
+pub fn month_grid_range(
+    month: jiff::civil::Date,
+    week_start: jiff::civil::Weekday,
+) -> (jiff::civil::Date, jiff::civil::Date) {
+    let (start, _) = week_range(month.first_of_month(), week_start);
+    let (_, end) = week_range(month.last_of_month(), week_start);
+    (start, end)
+}
+
+/// Territories where weeks start on Saturday, following CLDR.
+const SATURDAY_FIRST_TERRITORIES: &[&str] = &[
+    "AE", "AF", "BH", "DJ", "DZ", "EG", "IQ", "IR", "JO", "KW", "LY", "OM", "QA", "SD", "SY",
+];
+
+/// Territories where weeks start on Sunday, following CLDR.
+const SUNDAY_FIRST_TERRITORIES: &[&str] = &[
+    "AG", "AS", "BD", "BR", "BS", "BT", "BW", "BZ", "CA", "CN", "CO", "DM", "DO", "ET", "GT", "GU",
+    "HK", "HN", "ID", "IL", "IN", "JM", "JP", "KE", "KH", "KR", "LA", "MH", "MM", "MO", "MT", "MX",
+    "MZ", "NI", "NP", "PA", "PE", "PH", "PK", "PR", "PT", "PY", "SA", "SG", "SV", "TH", "TT", "TW",
+    "UM", "US", "VE", "VI", "WS", "YE", "ZA", "ZW",
+];
+
+/// The first day of the week for the given POSIX locale name, such as `en_US.UTF-8`.
+///
+/// Locales without a territory, and territories not known to start weeks on another day, start
+/// weeks on Monday.
+pub fn locale_first_weekday_for(locale: &str) -> jiff::civil::Weekday {
+    let territory = locale
+        .split(['.', '@'])
+        .next()
+        .and_then(|locale| locale.split_once('_'))
+        .map(|(_, territory)| territory);
+
+    match territory {
+        Some(territory) if SATURDAY_FIRST_TERRITORIES.contains(&territory) => {
+            jiff::civil::Weekday::Saturday
+        }
+        Some(territory) if SUNDAY_FIRST_TERRITORIES.contains(&territory) => {
+            jiff::civil::Weekday::Sunday
+        }
+        _ => jiff::civil::Weekday::Monday,
+    }
+}
+
+/// The first day of the week for the locale of the user, to pass to [`week_range`] and
+/// [`month_grid_range`].
+///
+/// The locale used for formatting dates (`LC_TIME`) is consulted.
+pub fn locale_first_weekday() -> jiff::civil::Weekday {
+    glib::language_names_with_category("LC_TIME")
+        .first()
+        .map_or(jiff::civil::Weekday::Monday, |locale| {
+            locale_first_weekday_for(locale)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::{Weekday, date};
+
+    use super::*;
+
+    #[test]
+    fn db_strings_round_trip_keeps_time_zone() {
+        let start = "2024-06-01T09:00:00-04:00[America/New_York]"
+            .parse::<Zoned>()
+            .unwrap();
+        let end = "2024-06-01T10:00:00-04:00[America/New_York]"
+            .parse::<Zoned>()
+            .unwrap();
+        let timeframe = Timeframe::new(false, start.clone(), end);
+
+        let (db_start, _) = timeframe.to_db_strings();
+        let parsed = db_start.parse::<Zoned>().unwrap();
+
+        assert_eq!(parsed.0.time_zone().iana_name(), Some("America/New_York"));
+        assert_eq!(parsed.0.timestamp(), start.0.timestamp());
+        assert_eq!(parsed.to_string(), start.to_string());
+    }
+
+    #[test]
+    fn all_day_variant_round_trips() {
+        let timeframe = all_day(date(2024, 6, 1), date(2024, 6, 3));
+
+        let variant = timeframe.to_variant();
+        assert_eq!(
+            variant.get::<(bool, String, String, String)>(),
+            Some((
+                true,
+                "2024-06-01".to_string(),
+                "2024-06-03".to_string(),
+                "UTC".to_string()
+            ))
+        );
+
+        let parsed = Timeframe::from_variant(&variant).unwrap();
+        assert!(parsed.all_day());
+        assert_eq!(parsed.start().date(), date(2024, 6, 1));
+        assert_eq!(parsed.end().date(), date(2024, 6, 3));
+    }
+
+    #[test]
+    fn zoned_variant_round_trips() {
+        let start = "2024-06-01T09:00:00-04:00[America/New_York]"
+            .parse::<Zoned>()
+            .unwrap();
+        let end = "2024-06-01T10:30:00-04:00[America/New_York]"
+            .parse::<Zoned>()
+            .unwrap();
+        let timeframe = Timeframe::new(false, start.clone(), end.clone());
+
+        let variant = timeframe.to_variant();
+        assert_eq!(
+            variant.get::<(bool, String, String, String)>(),
+            Some((
+                false,
+                "2024-06-01T09:00:00".to_string(),
+                "2024-06-01T10:30:00".to_string(),
+                "America/New_York".to_string()
+            ))
+        );
+
+        let parsed = Timeframe::from_variant(&variant).unwrap();
+        assert!(!parsed.all_day());
+        assert_eq!(parsed.start(), start);
+        assert_eq!(parsed.end(), end);
+    }
+
+    #[test]
+    fn malformed_variants_are_rejected() {
+        let wrong_type = ("2024-06-01", "2024-06-02").to_variant();
+        let invalid_date = (true, "2024-13-01", "2024-06-02", "UTC").to_variant();
+        let invalid_datetime =
+            (false, "2024-06-01 nine", "2024-06-01T10:00:00", "UTC").to_variant();
+        let unknown_zone = (
+            false,
+            "2024-06-01T09:00:00",
+            "2024-06-01T10:00:00",
+            "Nowhere/Special",
+        )
+            .to_variant();
+
+        for variant in [wrong_type, invalid_date, invalid_datetime, unknown_zone] {
+            assert!(Timeframe::from_variant(&variant).is_none(), "{variant}");
+        }
+    }
+
+    fn humanize(instant: &str, now: &str) -> String {
+        instant
+            .parse::<Zoned>()
+            .unwrap()
+            .humanize_relative(&now.parse().unwrap())
+    }
+
+    #[test]
+    fn humanize_relative_counts_minutes_under_an_hour() {
+        let now = "2024-06-01T12:00:00[UTC]";
+        assert_eq!(humanize("2024-06-01T12:00:29[UTC]", now), "now");
+        assert_eq!(humanize("2024-06-01T12:00:30[UTC]", now), "in 1 minute");
+        assert_eq!(humanize("2024-06-01T12:59:00[UTC]", now), "in 59 minutes");
+        assert_eq!(humanize("2024-06-01T11:01:00[UTC]", now), "59 minutes ago");
+        assert_eq!(humanize("2024-06-01T13:00:00[UTC]", now), "in 1 hour");
+    }
+
+    #[test]
+    fn humanize_relative_never_counts_24_hours() {
+        let now = "2024-06-01T00:10:00[UTC]";
+        assert_eq!(humanize("2024-06-01T23:20:00[UTC]", now), "in 23 hours");
+        // 23 hours and 40 minutes round to a day
+        assert_eq!(humanize("2024-06-01T23:50:00[UTC]", now), "today");
+        assert_eq!(
+            humanize("2024-06-02T00:00:00[UTC]", "2024-06-01T00:20:00[UTC]"),
+            "tomorrow"
+        );
+        assert_eq!(
+            humanize("2024-05-31T00:30:00[UTC]", "2024-06-01T00:10:00[UTC]"),
+            "yesterday"
+        );
+    }
+
+    #[test]
+    fn humanize_relative_never_counts_zero_days() {
+        // The day DST ends in Paris lasts 25 hours
+        let now = "2024-10-27T00:10:00+02:00[Europe/Paris]";
+        assert_eq!(
+            humanize("2024-10-27T23:10:00+01:00[Europe/Paris]", now),
+            "today"
+        );
+        assert_eq!(
+            humanize("2024-10-29T12:00:00+01:00[Europe/Paris]", now),
+            "in 2 days"
+        );
+        assert_eq!(
+            humanize("2024-10-24T12:00:00+02:00[Europe/Paris]", now),
+            "3 days ago"
+        );
+    }
+
+    #[test]
+    fn week_range_depends_on_week_start() {
+        let wednesday = date(2024, 6, 5);
+        assert_eq!(
+            week_range(wednesday, Weekday::Monday),
+            (date(2024, 6, 3), date(2024, 6, 9))
+        );
+        assert_eq!(
+            week_range(wednesday, Weekday::Sunday),
+            (date(2024, 6, 2), date(2024, 6, 8))
+        );
+
+        let sunday = date(2024, 6, 9);
+        assert_eq!(
+            week_range(sunday, Weekday::Monday),
+            (date(2024, 6, 3), date(2024, 6, 9))
+        );
+        assert_eq!(
+            week_range(sunday, Weekday::Sunday),
+            (date(2024, 6, 9), date(2024, 6, 15))
+        );
+    }
+
+    #[test]
+    fn month_grid_range_spans_year_end() {
+        assert_eq!(
+            month_grid_range(date(2024, 12, 15), Weekday::Monday),
+            (date(2024, 11, 25), date(2025, 1, 5))
+        );
+        assert_eq!(
+            month_grid_range(date(2024, 12, 15), Weekday::Sunday),
+            (date(2024, 12, 1), date(2025, 1, 4))
+        );
+    }
+
+    #[test]
+    fn month_grid_range_handles_leap_years() {
+        assert_eq!(
+            month_grid_range(date(2024, 2, 10), Weekday::Monday),
+            (date(2024, 1, 29), date(2024, 3, 3))
+        );
+        assert_eq!(
+            month_grid_range(date(2023, 2, 10), Weekday::Monday),
+            (date(2023, 1, 30), date(2023, 3, 5))
+        );
+    }
+
+    fn all_day(start: jiff::civil::Date, end: jiff::civil::Date) -> Timeframe {
+        Timeframe::from_instants(Instant::Date(start), Instant::Date(end)).unwrap()
+    }
+
+    #[test]
+    fn all_day_count_excludes_end() {
+        assert_eq!(all_day(date(2024, 6, 1), date(2024, 6, 2)).day_count(), 1);
+        assert_eq!(all_day(date(2024, 6, 1), date(2024, 6, 4)).day_count(), 3);
+    }
+
+    #[test]
+    fn all_day_overlaps_excludes_end() {
+        let one_day = all_day(date(2024, 6, 1), date(2024, 6, 2));
+        let next_day = all_day(date(2024, 6, 2), date(2024, 6, 3));
+        assert!(!one_day.overlaps(&next_day));
+        assert!(!next_day.overlaps(&one_day));
+
+        let three_days = all_day(date(2024, 6, 1), date(2024, 6, 4));
+        let last_day = all_day(date(2024, 6, 3), date(2024, 6, 4));
+        let day_after = all_day(date(2024, 6, 4), date(2024, 6, 5));
+        assert!(three_days.overlaps(&one_day));
+        assert!(three_days.overlaps(&last_day));
+        assert!(!three_days.overlaps(&day_after));
+        assert!(!day_after.overlaps(&three_days));
+    }
+
+    #[test]
+    fn first_weekday_follows_territory() {
+        assert_eq!(locale_first_weekday_for("en_US.UTF-8"), Weekday::Sunday);
+        assert_eq!(locale_first_weekday_for("fr_FR.UTF-8"), Weekday::Monday);
+        assert_eq!(locale_first_weekday_for("ar_EG"), Weekday::Saturday);
+        assert_eq!(locale_first_weekday_for("de_DE@euro"), Weekday::Monday);
+        assert_eq!(locale_first_weekday_for("C"), Weekday::Monday);
+    }
+}
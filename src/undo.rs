@@ -0,0 +1,182 @@
+use gdk::RGBA;
+
+use crate::{
+    Attendee, Event, EventTransparency, Manager, RecurrenceRule, Timeframe, WriteError, Zoned,
+};
+
+/// The fields of a deleted event, to create it again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeletedEvent {
+    /// The URI of the event. It changes each time the event is created again.
+    pub uri: String,
+    pub calendar_uri: String,
+    pub name: String,
+    pub description: String,
+    pub timeframe: Timeframe,
+    pub color: Option<RGBA>,
+    pub transparency: EventTransparency,
+    pub exdates: Vec<Zoned>,
+    pub attendees: Vec<Attendee>,
+    pub recurrence: Option<RecurrenceRule>,
+    pub recurrence_of: Option<String>,
+    pub recurrence_id: Option<Zoned>,
+}
+
+impl DeletedEvent {
+    /// Capture the fields of an event about to be deleted.
+    pub(crate) fn from_event(event: &Event) -> Self {
+        Self {
+            uri: event.uri(),
+            calendar_uri: event.calendar().uri(),
+            name: event.name(),
+            description: event.description(),
+            timeframe: event.timeframe().unwrap_or_default(),
+            color: event.color(),
+            transparency: event.transparency(),
+            exdates: event.exdates(),
+            attendees: event.attendees(),
+            recurrence: event.recurrence(),
+            recurrence_of: event.recurrence_of(),
+            recurrence_id: event.recurrence_id(),
+        }
+    }
+}
+
+/// A write that was made through the manager, and that can be undone and redone.
+///
+/// Each action records the state of the resource both before and after the write, so that it
+/// can be replayed in either direction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoAction {
+    /// A calendar was moved to the trash.
+    TrashCalendar { uri: String },
+    /// A calendar was taken out of the trash.
+    RestoreCalendar { uri: String },
+    /// A calendar was renamed or recolored.
+    UpdateCalendar {
+        uri: String,
+        old_name: String,
+        old_color: RGBA,
+        new_name: String,
+        new_color: RGBA,
+    },
+    /// The color override of an event was changed.
+    SetEventColor {
+        uri: String,
+        old_color: Option<RGBA>,
+        new_color: Option<RGBA>,
+    },
+    /// An event was deleted.
+    DeleteEvent { event: DeletedEvent },
+    /// A deleted event was created again from its fields.
+    RestoreEvent { event: DeletedEvent },
+}
+
+impl UndoAction {
+    /// The action reverting this one.
+    pub(crate) fn inverse(&self) -> Self {
+        match self {
+            Self::TrashCalendar { uri } => Self::RestoreCalendar { uri: uri.clone() },
+            Self::RestoreCalendar { uri } => Self::TrashCalendar { uri: uri.clone() },
+            Self::UpdateCalendar {
+                uri,
+                old_name,
+                old_color,
+                new_name,
+                new_color,
+            } => Self::UpdateCalendar {
+                uri: uri.clone(),
+                old_name: new_name.clone(),
+                old_color: *new_color,
+                new_name: old_name.clone(),
+                new_color: *old_color,
+            },
+            Self::SetEventColor {
+                uri,
+                old_color,
+                new_color,
+            } => Self::SetEventColor {
+                uri: uri.clone(),
+                old_color: *new_color,
+                new_color: *old_color,
+            },
+            Self::DeleteEvent { event } => Self::RestoreEvent {
+                event: event.clone(),
+            },
+            Self::RestoreEvent { event } => Self::DeleteEvent {
+                event: event.clone(),
+            },
+        }
+    }
+
+    /// Make the write described by this action through the write path of the manager.
+    ///
+    /// Returns the URI of the resource created by the write, if any.
+    pub(crate) fn apply(&self, manager: &Manager) -> Result<Option<String>, WriteError> {
+        match self {
+            Self::TrashCalendar { uri } => manager.update_calendar_trashed(uri, true)?,
+            Self::RestoreCalendar { uri } => manager.update_calendar_trashed(uri, false)?,
+            Self::UpdateCalendar {
+                uri,
+                new_name,
+                new_color,
+                ..
+            } => manager.update_calendar(uri, Some(new_name), Some(*new_color))?,
+            Self::SetEventColor { uri, new_color, .. } => {
+                manager.update_event_color(uri, *new_color)?
+            }
+            Self::DeleteEvent { event } => manager.delete_event(&event.uri)?,
+            Self::RestoreEvent { event } => return manager.restore_event(event).map(Some),
+        }
+
+        Ok(None)
+    }
+
+    /// Point this action to the resource created by a write, so that replaying it targets that
+    /// resource rather than the one it replaced.
+    pub(crate) fn set_created_uri(&mut self, uri: String) {
+        match self {
+            Self::DeleteEvent { event } | Self::RestoreEvent { event } => event.uri = uri,
+            Self::TrashCalendar { .. }
+            | Self::RestoreCalendar { .. }
+            | Self::UpdateCalendar { .. }
+            | Self::SetEventColor { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restoring_a_deleted_event_targets_its_new_uri() {
+        let event = DeletedEvent {
+            uri: "urn:event".to_string(),
+            calendar_uri: "urn:calendar".to_string(),
+            name: "Event".to_string(),
+            description: String::new(),
+            timeframe: Timeframe::default(),
+            color: None,
+            transparency: EventTransparency::Transparent,
+            exdates: Vec::new(),
+            attendees: Vec::new(),
+            recurrence: None,
+            recurrence_of: None,
+            recurrence_id: None,
+        };
+        let mut action = UndoAction::DeleteEvent { event };
+        assert!(matches!(
+            action.inverse(),
+            UndoAction::RestoreEvent { event } if event.uri == "urn:event"
+        ));
+
+        action.set_created_uri("urn:restored".to_string());
+        let UndoAction::RestoreEvent { event: restored } = action.inverse() else {
+            panic!("Inverse of a deletion should restore the event");
+        };
+        assert_eq!(restored.uri, "urn:restored");
+        assert_eq!(restored.calendar_uri, "urn:calendar");
+        assert_eq!(restored.transparency, EventTransparency::Transparent);
+    }
+}
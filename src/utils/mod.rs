@@ -1 +1,4 @@
 mod macros;
+mod signal_watches;
+
+pub(crate) use signal_watches::SignalWatches;
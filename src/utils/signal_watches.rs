@@ -0,0 +1,71 @@
+use std::cell::RefCell;
+
+use gdk::glib::{self, Object, SignalHandlerId, prelude::*};
+
+/// The signal handlers connected to keep a derived model up to date, by object.
+///
+/// Models built from other objects connect to their signals, and must disconnect from them once
+/// they are dropped, or the handlers would run for nothing as long as these objects live. Objects
+/// are only referenced weakly, so that the handlers can hold the watches without cycles.
+#[derive(Debug, Default)]
+pub(crate) struct SignalWatches {
+    watches: RefCell<Vec<(glib::WeakRef<Object>, Vec<SignalHandlerId>)>>,
+}
+
+impl SignalWatches {
+    /// Connect handlers to `object` with `connect`, unless it is already watched.
+    pub(crate) fn watch<O: IsA<Object>>(
+        &self,
+        object: &O,
+        connect: impl FnOnce(&O) -> Vec<SignalHandlerId>,
+    ) {
+        let object_ref = object.upcast_ref::<Object>();
+        {
+            let mut watches = self.watches.borrow_mut();
+            watches.retain(|(watched, _)| watched.upgrade().is_some());
+            if watches
+                .iter()
+                .any(|(watched, _)| watched.upgrade().as_ref() == Some(object_ref))
+            {
+                return;
+            }
+        }
+
+        let handlers = connect(object);
+        self.watches
+            .borrow_mut()
+            .push((object_ref.downgrade(), handlers));
+    }
+
+    /// Disconnect the handlers connected to `object`, if any.
+    pub(crate) fn unwatch(&self, object: &impl IsA<Object>) {
+        let object = object.upcast_ref::<Object>();
+        let handlers = {
+            let mut watches = self.watches.borrow_mut();
+            let Some(index) = watches
+                .iter()
+                .position(|(watched, _)| watched.upgrade().as_ref() == Some(object))
+            else {
+                return;
+            };
+            watches.swap_remove(index).1
+        };
+
+        for handler in handlers {
+            object.disconnect(handler);
+        }
+    }
+
+    /// Disconnect every handler.
+    pub(crate) fn clear(&self) {
+        let watches = self.watches.take();
+        for (object, handlers) in watches {
+            let Some(object) = object.upgrade() else {
+                continue;
+            };
+            for handler in handlers {
+                object.disconnect(handler);
+            }
+        }
+    }
+}